@@ -0,0 +1,232 @@
+use super::{http, icons, index};
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Largest page or resource downloaded into a snapshot.
+const DOWNLOAD_LIMIT: u64 = 10 * 1024 * 1024;
+
+/// Options of page snapshots.
+pub struct ArchiveOptions {
+    /// Directory the snapshots are saved in.
+    pub archive_path: PathBuf,
+    /// Time to wait for every request.
+    pub timeout: Duration,
+}
+
+/// Snapshot directory of a configuration file, next to it.
+pub fn archive_path(config_path: &Path) -> PathBuf {
+    let mut archive_path = config_path.as_os_str().to_owned();
+    archive_path.push(".archive");
+    PathBuf::from(archive_path)
+}
+
+/// Path of a bookmark's snapshot, unique per bookmark name.
+pub fn snapshot_path(archive_path: &Path, name: &str) -> PathBuf {
+    archive_path.join(format!(
+        "{}-{:08x}.html",
+        icons::slug(name),
+        index::hash(name.as_bytes()) as u32
+    ))
+}
+
+/// Saves a page as a single HTML file with its stylesheets and images inlined, returning its path.
+///
+/// Scripts are removed and links are made absolute so the snapshot works offline.
+pub fn archive_page(name: &str, url: &str, options: &ArchiveOptions) -> Result<PathBuf> {
+    let agent = http::agent(options.timeout);
+    let (page_url, html) = download_text(&agent, url)?;
+    let mut document = scraper::Html::parse_document(&html);
+    let mut resources = HashMap::new();
+
+    // Rewrite the document.
+    let node_ids: Vec<_> = document.tree.nodes().map(|node| node.id()).collect();
+    for node_id in node_ids {
+        let node = document.tree.get(node_id).unwrap();
+        let parent_name = node
+            .parent()
+            .and_then(|parent| parent.value().as_element())
+            .map(|parent| String::from(parent.name()));
+        let name = node
+            .value()
+            .as_element()
+            .map(|element| String::from(element.name()));
+
+        // Scripts would change the snapshot and a base would point links back at the website.
+        let mut node = document.tree.get_mut(node_id).unwrap();
+        if let Some("script" | "base") = name.as_deref() {
+            node.detach();
+            continue;
+        }
+
+        match node.value() {
+            scraper::Node::Element(element) => {
+                let tag = name.unwrap_or_default();
+                let rel = element.attr("rel").unwrap_or("").to_lowercase();
+                let rels: Vec<&str> = rel.split_whitespace().collect();
+                for (attr_name, value) in element.attrs.iter_mut() {
+                    let inlined = match (tag.as_str(), &*attr_name.local) {
+                        ("link", "href") if rels.contains(&"stylesheet") => {
+                            inline_stylesheet(&agent, &page_url, value, &mut resources)
+                        }
+                        ("link", "href") if rels.iter().any(|rel| rel.contains("icon")) => {
+                            inline_resource(&agent, &page_url, value, &mut resources)
+                        }
+                        ("img" | "input" | "video", "src" | "poster") => {
+                            inline_resource(&agent, &page_url, value, &mut resources)
+                        }
+                        ("a" | "area" | "form" | "link", "href" | "action")
+                            if !value.starts_with('#') =>
+                        {
+                            page_url.join(value).ok().map(String::from)
+                        }
+                        (_, "style") => {
+                            Some(inline_css_urls(&agent, &page_url, value, &mut resources))
+                        }
+                        _ => None,
+                    };
+                    if let Some(inlined) = inlined {
+                        *value = inlined.into();
+                    }
+                }
+
+                // Responsive images would load from the website again.
+                element
+                    .attrs
+                    .retain(|attr_name, _| &*attr_name.local != "srcset");
+            }
+            scraper::Node::Text(text) if parent_name.as_deref() == Some("style") => {
+                let css = inline_css_urls(&agent, &page_url, &text.text, &mut resources);
+                text.text = css.into();
+            }
+            _ => {}
+        }
+    }
+
+    // Save.
+    std::fs::create_dir_all(&options.archive_path).with_context(|| {
+        format!(
+            "Unable to create archive directory '{}'.",
+            options.archive_path.display()
+        )
+    })?;
+    let snapshot_path = snapshot_path(&options.archive_path, name);
+    std::fs::write(&snapshot_path, document.html())
+        .with_context(|| format!("Unable to save '{}'.", snapshot_path.display()))?;
+
+    Ok(snapshot_path)
+}
+
+/// Downloads a text document, returning the url it was served from and its text.
+fn download_text(agent: &ureq::Agent, url: &str) -> Result<(url::Url, String)> {
+    let response = agent.get(url).call()?;
+    let content_type = String::from(response.content_type());
+    if !content_type.is_empty() && !content_type.contains("html") && !content_type.contains("xml") {
+        bail!("Page is '{}' instead of HTML.", content_type);
+    }
+    let page_url = url::Url::parse(response.get_url())?;
+    let body = http::read_body(response, DOWNLOAD_LIMIT)?;
+
+    Ok((page_url, String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// Downloads a resource as a `data:` url, reusing resources downloaded before.
+///
+/// Resources that can't be downloaded keep their absolute url.
+fn inline_resource(
+    agent: &ureq::Agent,
+    base_url: &url::Url,
+    reference: &str,
+    resources: &mut HashMap<String, String>,
+) -> Option<String> {
+    let resource_url = base_url.join(reference.trim()).ok()?;
+    if resource_url.scheme() == "data" {
+        return None;
+    }
+    if let Some(data_url) = resources.get(resource_url.as_str()) {
+        return Some(data_url.clone());
+    }
+
+    let data_url = match download(agent, &resource_url) {
+        Ok((content_type, body)) => data_url(&content_type, &body),
+        Err(e) => {
+            log::debug!("Unable to download '{}': {}", resource_url, e);
+            String::from(resource_url.clone())
+        }
+    };
+    resources.insert(String::from(resource_url), data_url.clone());
+
+    Some(data_url)
+}
+
+/// Downloads a stylesheet with the resources it references inlined as a `data:` url.
+fn inline_stylesheet(
+    agent: &ureq::Agent,
+    base_url: &url::Url,
+    reference: &str,
+    resources: &mut HashMap<String, String>,
+) -> Option<String> {
+    let stylesheet_url = base_url.join(reference.trim()).ok()?;
+    let css = match download(agent, &stylesheet_url) {
+        Ok((_, body)) => String::from_utf8_lossy(&body).into_owned(),
+        Err(e) => {
+            log::debug!("Unable to download '{}': {}", stylesheet_url, e);
+            return Some(String::from(stylesheet_url));
+        }
+    };
+    let css = inline_css_urls(agent, &stylesheet_url, &css, resources);
+
+    Some(data_url("text/css", css.as_bytes()))
+}
+
+/// CSS with the resources of its `url(...)` references inlined.
+fn inline_css_urls(
+    agent: &ureq::Agent,
+    base_url: &url::Url,
+    css: &str,
+    resources: &mut HashMap<String, String>,
+) -> String {
+    let mut inlined = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find("url(") {
+        let (before, after) = rest.split_at(start + "url(".len());
+        inlined.push_str(before);
+        let end = match after.find(')') {
+            Some(end) => end,
+            None => {
+                rest = after;
+                break;
+            }
+        };
+
+        let reference = after[..end].trim().trim_matches(|c| c == '"' || c == '\'');
+        match inline_resource(agent, base_url, reference, resources) {
+            Some(data_url) => inlined.push_str(&format!("\"{}\"", data_url)),
+            None => inlined.push_str(&after[..end]),
+        }
+        rest = &after[end..];
+    }
+    inlined.push_str(rest);
+
+    inlined
+}
+
+/// Downloads a resource, returning its content type and body.
+fn download(agent: &ureq::Agent, url: &url::Url) -> Result<(String, Vec<u8>)> {
+    let response = agent.get(url.as_str()).call()?;
+    let content_type = String::from(response.content_type());
+    let body = http::read_body(response, DOWNLOAD_LIMIT)?;
+
+    Ok((content_type, body))
+}
+
+/// Base64 `data:` url of a resource.
+fn data_url(content_type: &str, data: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        content_type,
+        base64::engine::general_purpose::STANDARD.encode(data)
+    )
+}
@@ -0,0 +1,326 @@
+use super::index::hash;
+use image::{Rgba, RgbaImage};
+
+/// Background colors of generated icons, picked by hash so every key keeps its color.
+const PALETTE: &[[u8; 3]] = &[
+    [0xe5, 0x39, 0x35],
+    [0xd8, 0x1b, 0x60],
+    [0x8e, 0x24, 0xaa],
+    [0x5e, 0x35, 0xb1],
+    [0x39, 0x49, 0xab],
+    [0x1e, 0x88, 0xe5],
+    [0x03, 0x9b, 0xe5],
+    [0x00, 0x89, 0x7b],
+    [0x43, 0xa0, 0x47],
+    [0x7c, 0xb3, 0x42],
+    [0xf4, 0x51, 0x1e],
+    [0x6d, 0x4c, 0x41],
+    [0x54, 0x6e, 0x7a],
+];
+
+/// Width in pixels of a glyph of `FONT`.
+const GLYPH_WIDTH: u32 = 5;
+/// Height in pixels of a glyph of `FONT`.
+const GLYPH_HEIGHT: u32 = 7;
+
+/// 5x7 bitmap font, one byte per row with the leftmost pixel in the highest of the five bits.
+const FONT: &[(char, [u8; 7])] = &[
+    (
+        'A',
+        [
+            0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'B',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+        ],
+    ),
+    (
+        'C',
+        [
+            0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'D',
+        [
+            0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110,
+        ],
+    ),
+    (
+        'E',
+        [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        'F',
+        [
+            0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+    ),
+    (
+        'G',
+        [
+            0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+        ],
+    ),
+    (
+        'H',
+        [
+            0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'I',
+        [
+            0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+    ),
+    (
+        'J',
+        [
+            0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+        ],
+    ),
+    (
+        'K',
+        [
+            0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+        ],
+    ),
+    (
+        'L',
+        [
+            0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        'M',
+        [
+            0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'N',
+        [
+            0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'O',
+        [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'P',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+        ],
+    ),
+    (
+        'Q',
+        [
+            0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+        ],
+    ),
+    (
+        'R',
+        [
+            0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+        ],
+    ),
+    (
+        'S',
+        [
+            0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+        ],
+    ),
+    (
+        'T',
+        [
+            0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+        ],
+    ),
+    (
+        'U',
+        [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        'V',
+        [
+            0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+        ],
+    ),
+    (
+        'W',
+        [
+            0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+        ],
+    ),
+    (
+        'X',
+        [
+            0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+        ],
+    ),
+    (
+        'Y',
+        [
+            0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100,
+        ],
+    ),
+    (
+        'Z',
+        [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+        ],
+    ),
+    (
+        '0',
+        [
+            0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '1',
+        [
+            0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+        ],
+    ),
+    (
+        '2',
+        [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+        ],
+    ),
+    (
+        '3',
+        [
+            0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '4',
+        [
+            0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+        ],
+    ),
+    (
+        '5',
+        [
+            0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '6',
+        [
+            0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '7',
+        [
+            0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+        ],
+    ),
+    (
+        '8',
+        [
+            0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+        ],
+    ),
+    (
+        '9',
+        [
+            0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+        ],
+    ),
+    (
+        '?',
+        [
+            0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+        ],
+    ),
+];
+
+/// Up to two initials of a hostname or bookmark name, `?` when there are none.
+///
+/// Hostnames use the words of their first label without `www`, so `my-wiki.corp` is `MW`.
+pub fn initials(text: &str, is_hostname: bool) -> String {
+    let text = if is_hostname {
+        text.split(['.', ':'])
+            .find(|label| !label.is_empty() && *label != "www")
+            .unwrap_or("")
+    } else {
+        text
+    };
+
+    let initials: String = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter_map(|word| word.chars().next())
+        .take(2)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    if initials.is_empty() {
+        String::from("?")
+    } else {
+        initials
+    }
+}
+
+/// Renders the initials on a circle with a color derived from the key.
+pub fn render_avatar(key: &str, initials: &str, size: u32) -> RgbaImage {
+    let [red, green, blue] = PALETTE[(hash(key.as_bytes()) % PALETTE.len() as u64) as usize];
+    let mut icon = RgbaImage::new(size, size);
+
+    // Antialiased circle.
+    let radius = size as f32 / 2.0;
+    for (x, y, pixel) in icon.enumerate_pixels_mut() {
+        let distance =
+            ((x as f32 + 0.5 - radius).powi(2) + (y as f32 + 0.5 - radius).powi(2)).sqrt();
+        let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+        *pixel = Rgba([red, green, blue, (coverage * 255.0) as u8]);
+    }
+
+    // Glyphs scaled to fill about half of the icon, one pixel column apart.
+    let glyphs: Vec<&[u8; 7]> = initials
+        .chars()
+        .map(|c| {
+            FONT.iter()
+                .find(|(glyph_char, _)| *glyph_char == c)
+                .or_else(|| FONT.iter().find(|(glyph_char, _)| *glyph_char == '?'))
+                .map(|(_, glyph)| glyph)
+                .unwrap()
+        })
+        .collect();
+    let columns = glyphs.len() as u32 * (GLYPH_WIDTH + 1) - 1;
+    let scale = (size / 2 / GLYPH_HEIGHT).min(size * 2 / 3 / columns).max(1);
+    let left = (size - columns * scale) / 2;
+    let top = (size - GLYPH_HEIGHT * scale) / 2;
+    for (position, glyph) in glyphs.iter().enumerate() {
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+
+                let x = left + (position as u32 * (GLYPH_WIDTH + 1) + column) * scale;
+                let y = top + row as u32 * scale;
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        icon.put_pixel(x + dx, y + dy, Rgba([255, 255, 255, 255]));
+                    }
+                }
+            }
+        }
+    }
+
+    icon
+}
@@ -0,0 +1,103 @@
+use super::import::ImportedBookmark;
+use super::Config;
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Table of buku's database, created when exporting to a new database.
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS bookmarks (
+    id integer PRIMARY KEY,
+    URL text NOT NULL UNIQUE,
+    metadata text default '',
+    tags text default ',',
+    desc text default '',
+    flags integer default 0
+)";
+
+/// Reads the bookmarks of a buku database, usually `~/.local/share/buku/bookmarks.db`.
+///
+/// The title of a bookmark becomes its name, tags and descriptions are kept.
+pub fn read(path: &Path) -> Result<Vec<ImportedBookmark>> {
+    if !path.is_file() {
+        bail!("No buku database found at '{}'.", path.display());
+    }
+
+    read_database(path).with_context(|| format!("Unable to read '{}'.", path.display()))
+}
+
+fn read_database(path: &Path) -> Result<Vec<ImportedBookmark>> {
+    let connection =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut statement = connection.prepare(
+        "SELECT URL, IFNULL(metadata, ''), IFNULL(tags, ''), IFNULL(desc, '')
+         FROM bookmarks ORDER BY id",
+    )?;
+
+    let mut bookmarks = Vec::new();
+    for row in statement.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+        ))
+    })? {
+        let (url, title, tags, description) = row?;
+        bookmarks.push(ImportedBookmark {
+            name: title,
+            url,
+            // Tags are stored as `,tag1,tag2,`.
+            tags: tags
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect(),
+            description: Some(description).filter(|description| !description.trim().is_empty()),
+            ..ImportedBookmark::default()
+        });
+    }
+
+    Ok(bookmarks)
+}
+
+/// Writes the bookmarks into a buku database, creating it when it doesn't exist.
+///
+/// Bookmarks of urls already in the database are updated, other entries are kept. Returns the
+/// number of written bookmarks.
+pub fn write(path: &Path, config: &Config) -> Result<usize> {
+    write_database(path, config).with_context(|| format!("Unable to write '{}'.", path.display()))
+}
+
+fn write_database(path: &Path, config: &Config) -> Result<usize> {
+    let mut connection = rusqlite::Connection::open(path)?;
+    connection.execute(CREATE_TABLE, [])?;
+
+    let transaction = connection.transaction()?;
+    {
+        let mut statement = transaction.prepare(
+            "INSERT INTO bookmarks (URL, metadata, tags, desc) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(URL) DO UPDATE SET
+               metadata = excluded.metadata, tags = excluded.tags, desc = excluded.desc",
+        )?;
+        for (name, bookmark) in config.bookmarks.iter() {
+            let tags: Vec<String> = bookmark
+                .tags
+                .iter()
+                .map(|tag| tag.replace(',', " "))
+                .collect();
+            statement.execute((
+                &bookmark.url,
+                name,
+                if tags.is_empty() {
+                    String::from(",")
+                } else {
+                    format!(",{},", tags.join(","))
+                },
+                bookmark.description.as_deref().unwrap_or(""),
+            ))?;
+        }
+    }
+    transaction.commit()?;
+
+    Ok(config.bookmarks.len())
+}
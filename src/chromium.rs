@@ -0,0 +1,99 @@
+use super::import::ImportedBookmark;
+use anyhow::{bail, Context, Result};
+use chrono::TimeZone;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Roots of the bookmark tree, which aren't folders of their own.
+const ROOTS: &[&str] = &["bookmark_bar", "other", "synced"];
+
+/// Microseconds between 1601-01-01, the epoch of Chromium timestamps, and 1970-01-01.
+const EPOCH_OFFSET_MICROSECONDS: i64 = 11_644_473_600_000_000;
+
+/// Chromium `Bookmarks` file.
+#[derive(Debug, serde::Deserialize)]
+struct BookmarksFile {
+    roots: BTreeMap<String, Node>,
+}
+
+/// Bookmark or folder of a `Bookmarks` file.
+#[derive(Debug, serde::Deserialize)]
+struct Node {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    url: String,
+    /// Microseconds since 1601-01-01 as a string.
+    #[serde(default)]
+    date_added: String,
+    #[serde(default)]
+    children: Vec<Node>,
+}
+
+/// Reads the bookmarks of a Chromium, Chrome, Brave or Edge profile directory or `Bookmarks` file.
+///
+/// The bookmark bar, other and synced bookmarks are read, their folders are kept.
+pub fn read(path: &Path) -> Result<Vec<ImportedBookmark>> {
+    let bookmarks_path = if path.is_dir() {
+        path.join("Bookmarks")
+    } else {
+        path.to_path_buf()
+    };
+    if !bookmarks_path.is_file() {
+        bail!(
+            "No Chromium bookmarks found at '{}'.",
+            bookmarks_path.display()
+        );
+    }
+
+    let contents = std::fs::read_to_string(&bookmarks_path)
+        .with_context(|| format!("Unable to read '{}'.", bookmarks_path.display()))?;
+    parse(&contents).with_context(|| format!("Unable to read '{}'.", bookmarks_path.display()))
+}
+
+/// Reads the bookmarks of the contents of a `Bookmarks` file.
+fn parse(contents: &str) -> Result<Vec<ImportedBookmark>> {
+    let bookmarks_file: BookmarksFile = serde_json::from_str(contents)?;
+
+    let mut bookmarks = Vec::new();
+    for root_name in ROOTS {
+        // Roots are missing from files of older versions.
+        if let Some(root) = bookmarks_file.roots.get(*root_name) {
+            walk(&root.children, &mut Vec::new(), &mut bookmarks);
+        }
+    }
+
+    Ok(bookmarks)
+}
+
+/// Reads the bookmarks of a folder and its subfolders.
+fn walk(nodes: &[Node], folders: &mut Vec<String>, bookmarks: &mut Vec<ImportedBookmark>) {
+    for node in nodes {
+        match node.kind.as_str() {
+            "url" if !node.url.is_empty() => bookmarks.push(ImportedBookmark {
+                name: node.name.clone(),
+                url: node.url.clone(),
+                folders: folders.clone(),
+                created: node
+                    .date_added
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|microseconds| *microseconds > 0)
+                    .and_then(|microseconds| {
+                        chrono::Utc
+                            .timestamp_micros(microseconds - EPOCH_OFFSET_MICROSECONDS)
+                            .single()
+                    }),
+                ..ImportedBookmark::default()
+            }),
+            "folder" => {
+                folders.push(node.name.clone());
+                walk(&node.children, folders, bookmarks);
+                folders.pop();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use super::{http, index, urls, Config};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Version of the content index format, bumped whenever `ContentIndex` changes shape.
+const CONTENT_INDEX_VERSION: u32 = 1;
+
+/// Largest page downloaded for its text.
+const DOWNLOAD_LIMIT: u64 = 5 * 1024 * 1024;
+
+/// Characters of page text kept for snippets.
+const TEXT_LIMIT: usize = 100_000;
+
+/// Characters shown around the first match of a snippet.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// BM25 term frequency saturation.
+const BM25_K1: f64 = 1.2;
+
+/// BM25 page length normalization.
+const BM25_B: f64 = 0.75;
+
+/// Elements whose text isn't part of the readable content of a page.
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "template", "nav", "header", "footer", "aside", "form",
+];
+
+/// Options of the content index.
+pub struct ContentOptions {
+    /// Age after which pages are downloaded again.
+    pub ttl: Duration,
+    /// Time to wait for every request.
+    pub timeout: Duration,
+    /// Number of pages downloaded at the same time.
+    pub jobs: usize,
+}
+
+/// Readable text of bookmarked pages, stored next to the configuration file.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ContentIndex {
+    version: u32,
+    entries: BTreeMap<String, ContentEntry>,
+}
+
+/// Text of a bookmarked page.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct ContentEntry {
+    url: String,
+    /// When the page was downloaded, in seconds since the epoch.
+    fetched: i64,
+    /// Readable text of the page, empty when it couldn't be downloaded.
+    text: String,
+    /// Number of occurrences of every token of the text.
+    tokens: BTreeMap<String, u32>,
+    /// Number of tokens of the text.
+    length: u32,
+}
+
+/// Bookmark whose page matches a content search.
+#[derive(Debug)]
+pub struct ContentMatch<'a> {
+    pub name: &'a str,
+    pub url: &'a str,
+    pub score: f64,
+    /// Text around the first match.
+    pub snippet: String,
+}
+
+/// Path of the content index for a configuration file.
+fn content_index_path(config_path: &Path) -> PathBuf {
+    let mut content_index_path = config_path.as_os_str().to_owned();
+    content_index_path.push(".content");
+    PathBuf::from(content_index_path)
+}
+
+/// Loads the content index, starting over with missing, corrupt or outdated ones.
+pub fn load_content_index(config_path: &Path) -> ContentIndex {
+    std::fs::read(content_index_path(config_path))
+        .ok()
+        .and_then(|contents| bincode::deserialize::<ContentIndex>(&contents).ok())
+        .filter(|content_index| content_index.version == CONTENT_INDEX_VERSION)
+        .unwrap_or(ContentIndex {
+            version: CONTENT_INDEX_VERSION,
+            entries: BTreeMap::new(),
+        })
+}
+
+/// Saves the content index atomically.
+pub fn save_content_index(config_path: &Path, content_index: &ContentIndex) -> Result<()> {
+    let content_index_path = content_index_path(config_path);
+    let mut temporary_path = content_index_path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+
+    std::fs::write(&temporary_path, bincode::serialize(content_index)?)?;
+    std::fs::rename(&temporary_path, &content_index_path)?;
+
+    Ok(())
+}
+
+impl ContentIndex {
+    /// Downloads the pages of new, changed and stale web bookmarks and forgets deleted ones.
+    ///
+    /// Returns the number of downloaded pages.
+    pub fn refresh(&mut self, config: &Config, options: &ContentOptions) -> usize {
+        let now = chrono::Utc::now().timestamp();
+        self.entries
+            .retain(|name, entry| match config.bookmarks.get(name) {
+                Some(bookmark) => bookmark.url == entry.url,
+                None => false,
+            });
+
+        let stale: Vec<(&String, &String)> = config
+            .bookmarks
+            .iter()
+            .filter(|(_, bookmark)| {
+                urls::parse(&bookmark.url)
+                    .map(|url| url.scheme() == "http" || url.scheme() == "https")
+                    .unwrap_or(false)
+            })
+            .filter(|(name, _)| match self.entries.get(*name) {
+                Some(entry) => now - entry.fetched >= options.ttl.as_secs() as i64,
+                None => true,
+            })
+            .map(|(name, bookmark)| (name, &bookmark.url))
+            .collect();
+
+        // Download.
+        let agent = http::agent(options.timeout);
+        let queue = Mutex::new(stale.iter());
+        let entries = Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            for _ in 0..options.jobs.max(1) {
+                scope.spawn(|| loop {
+                    let next = queue.lock().unwrap().next();
+                    let (name, url) = match next {
+                        Some(page) => page,
+                        None => break,
+                    };
+
+                    let text = match fetch_text(&agent, url) {
+                        Ok(text) => text,
+                        Err(e) => {
+                            log::debug!("Unable to fetch '{}': {}", url, e);
+                            String::new()
+                        }
+                    };
+                    let entry = ContentEntry::new(url, now, text);
+                    entries.lock().unwrap().push((String::from(*name), entry));
+                });
+            }
+        });
+
+        let entries = entries.into_inner().unwrap();
+        let fetched = entries.len();
+        self.entries.extend(entries);
+        fetched
+    }
+
+    /// Bookmarks whose page contains every term of the query as a word prefix, best first.
+    ///
+    /// Pages are ranked with BM25 so rare terms and short pages count more.
+    pub fn search(&self, query: &str) -> Vec<ContentMatch<'_>> {
+        let terms: Vec<String> = index::tokenize(query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let pages = self.entries.len() as f64;
+        let average_length = self
+            .entries
+            .values()
+            .map(|entry| f64::from(entry.length))
+            .sum::<f64>()
+            / pages.max(1.0);
+
+        // Number of occurrences of every term per page.
+        let occurrences: Vec<Vec<u32>> = terms
+            .iter()
+            .map(|term| {
+                self.entries
+                    .values()
+                    .map(|entry| {
+                        entry
+                            .tokens
+                            .range(term.clone()..)
+                            .take_while(|(token, _)| token.starts_with(term.as_str()))
+                            .map(|(_, count)| count)
+                            .sum()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut matches: Vec<ContentMatch> = Vec::new();
+        for (position, (name, entry)) in self.entries.iter().enumerate() {
+            if occurrences.iter().any(|counts| counts[position] == 0) {
+                continue;
+            }
+
+            let mut score = 0.0;
+            for counts in occurrences.iter() {
+                let pages_with_term = counts.iter().filter(|count| **count > 0).count() as f64;
+                let idf = ((pages - pages_with_term + 0.5) / (pages_with_term + 0.5) + 1.0).ln();
+                let frequency = f64::from(counts[position]);
+                let length = f64::from(entry.length) / average_length.max(1.0);
+                score += idf * frequency * (BM25_K1 + 1.0)
+                    / (frequency + BM25_K1 * (1.0 - BM25_B + BM25_B * length));
+            }
+
+            matches.push(ContentMatch {
+                name,
+                url: &entry.url,
+                score,
+                snippet: snippet(&entry.text, &terms),
+            });
+        }
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.name.cmp(b.name)));
+
+        matches
+    }
+}
+
+impl ContentEntry {
+    fn new(url: &str, fetched: i64, text: String) -> ContentEntry {
+        let mut tokens: BTreeMap<String, u32> = BTreeMap::new();
+        let mut length = 0;
+        for token in index::tokenize(&text) {
+            *tokens.entry(token).or_default() += 1;
+            length += 1;
+        }
+
+        ContentEntry {
+            url: String::from(url),
+            fetched,
+            text,
+            tokens,
+            length,
+        }
+    }
+}
+
+/// Downloads a page and extracts its readable text.
+fn fetch_text(agent: &ureq::Agent, url: &str) -> Result<String> {
+    let response = agent.get(url).call()?;
+    let body = http::read_body(response, DOWNLOAD_LIMIT)?;
+
+    Ok(readable_text(&String::from_utf8_lossy(&body)))
+}
+
+/// Text of the article, main content or body of a page without navigation, scripts or styles.
+fn readable_text(html: &str) -> String {
+    let document = scraper::Html::parse_document(html);
+    let root = ["article", "main", "body"]
+        .iter()
+        .find_map(|name| {
+            document
+                .select(&scraper::Selector::parse(name).unwrap())
+                .next()
+        })
+        .unwrap_or_else(|| document.root_element());
+
+    let mut words: Vec<&str> = Vec::new();
+    for node in root.descendants() {
+        let text = match node.value().as_text() {
+            Some(text) => text,
+            None => continue,
+        };
+        let skipped = node.ancestors().any(|ancestor| {
+            ancestor
+                .value()
+                .as_element()
+                .is_some_and(|element| SKIPPED_ELEMENTS.contains(&element.name()))
+        });
+        if !skipped {
+            words.extend(text.split_whitespace());
+        }
+    }
+
+    let mut text = words.join(" ");
+    if let Some((end, _)) = text.char_indices().nth(TEXT_LIMIT) {
+        text.truncate(end);
+    }
+    text
+}
+
+/// Text around the first word of the text that a term prefixes.
+fn snippet(text: &str, terms: &[String]) -> String {
+    let start = text
+        .char_indices()
+        .filter(|(position, c)| {
+            c.is_alphanumeric()
+                && !text[..*position]
+                    .chars()
+                    .next_back()
+                    .is_some_and(char::is_alphanumeric)
+        })
+        .map(|(position, _)| position)
+        .find(|position| {
+            let word: String = text[*position..]
+                .chars()
+                .take_while(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase();
+            terms.iter().any(|term| word.starts_with(term.as_str()))
+        })
+        .unwrap_or(0);
+
+    // Widen to whole words around the match.
+    let mut snippet_start = start.saturating_sub(SNIPPET_CONTEXT);
+    while !text.is_char_boundary(snippet_start) {
+        snippet_start -= 1;
+    }
+    if snippet_start > 0 {
+        snippet_start = text[snippet_start..start]
+            .find(' ')
+            .map(|space| snippet_start + space + 1)
+            .unwrap_or(start);
+    }
+    let mut snippet_end = (start + SNIPPET_CONTEXT * 2).min(text.len());
+    while !text.is_char_boundary(snippet_end) {
+        snippet_end += 1;
+    }
+    if snippet_end < text.len() {
+        snippet_end = text[start..snippet_end]
+            .rfind(' ')
+            .map(|space| start + space)
+            .unwrap_or(snippet_end);
+    }
+
+    format!(
+        "{}{}{}",
+        if snippet_start > 0 { "…" } else { "" },
+        &text[snippet_start..snippet_end],
+        if snippet_end < text.len() { "…" } else { "" }
+    )
+}
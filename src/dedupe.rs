@@ -0,0 +1,171 @@
+use super::{load_config, prompt, save_config, urls, ConfigBookmark};
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// Names of the supported merge policies.
+pub const POLICIES: &[&str] = &["keep-oldest", "union-tags"];
+
+/// How duplicates are merged without asking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DedupePolicy {
+    /// Keep the oldest bookmark and delete the others.
+    KeepOldest,
+    /// Keep the oldest bookmark with the tags of every duplicate.
+    UnionTags,
+}
+
+impl std::str::FromStr for DedupePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Ok(match name {
+            "keep-oldest" => DedupePolicy::KeepOldest,
+            "union-tags" => DedupePolicy::UnionTags,
+            _ => bail!("Dedupe policy '{}' not supported.", name),
+        })
+    }
+}
+
+/// Options of the dedupe command.
+pub struct DedupeOptions {
+    /// Merge with a policy instead of asking, `None` asks for every group.
+    pub policy: Option<DedupePolicy>,
+    /// Only show the duplicates.
+    pub dry_run: bool,
+}
+
+/// Shows groups of bookmarks with the same url and merges each group into one bookmark.
+pub fn run(
+    config_path: &Path,
+    options: &DedupeOptions,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let mut config = load_config(config_path)?;
+
+    let groups = duplicate_groups(&config.bookmarks);
+    if groups.is_empty() {
+        writeln!(out, "No duplicate bookmarks found.")?;
+        return Ok(());
+    }
+
+    let mut deleted = 0;
+    for names in groups.iter() {
+        // Show the group, oldest first.
+        writeln!(out, "Duplicates of '{}':", config.bookmarks[&names[0]].url)?;
+        for (position, name) in names.iter().enumerate() {
+            let bookmark = &config.bookmarks[name];
+            writeln!(
+                out,
+                "  {}. {}\t{}\t{}",
+                position + 1,
+                name,
+                bookmark.tags.join(","),
+                bookmark.url
+            )?;
+        }
+        if options.dry_run {
+            continue;
+        }
+
+        // Choose the bookmark to keep.
+        let (keep, union_tags) = match options.policy {
+            Some(DedupePolicy::KeepOldest) => (0, false),
+            Some(DedupePolicy::UnionTags) => (0, true),
+            None => match ask_keep(input, out, names)? {
+                Some(keep) => (
+                    keep,
+                    prompt::confirm(input, out, &format!("Merge tags into '{}'?", names[keep]))?,
+                ),
+                None => continue,
+            },
+        };
+
+        // Merge.
+        let mut tags = config.bookmarks[&names[keep]].tags.clone();
+        for (position, name) in names.iter().enumerate() {
+            if position == keep {
+                continue;
+            }
+
+            let bookmark = config.bookmarks.remove(name).unwrap();
+            if union_tags {
+                for tag in bookmark.tags {
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+            }
+            writeln!(out, "Deleted bookmark '{}'.", name)?;
+            deleted += 1;
+        }
+        if union_tags {
+            config.bookmarks.get_mut(&names[keep]).unwrap().tags = tags;
+            writeln!(out, "Merged tags into bookmark '{}'.", names[keep])?;
+        }
+    }
+
+    // Save.
+    if options.dry_run {
+        writeln!(out, "Found {} groups of duplicate bookmarks.", groups.len())?;
+    } else {
+        if deleted > 0 {
+            save_config(config_path, &config)?;
+        }
+        writeln!(out, "Removed {} duplicate bookmarks.", deleted)?;
+    }
+
+    Ok(())
+}
+
+/// Names of bookmarks sharing a url, see `urls::duplicate_key`, oldest first.
+///
+/// Bookmarks added before creation times were recorded count as the oldest.
+fn duplicate_groups(bookmarks: &BTreeMap<String, ConfigBookmark>) -> Vec<Vec<String>> {
+    let options = urls::NormalizeOptions::from_env();
+
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, bookmark) in bookmarks.iter() {
+        groups
+            .entry(urls::duplicate_key(&bookmark.url, &options))
+            .or_default()
+            .push(name.clone());
+    }
+
+    groups
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .map(|mut names| {
+            // Stable so bookmarks added at the same time stay ordered by name.
+            names.sort_by_key(|name| bookmarks[name].created);
+            names
+        })
+        .collect()
+}
+
+/// Asks which bookmark of a group to keep, `None` skips the group.
+fn ask_keep(
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+    names: &[String],
+) -> Result<Option<usize>> {
+    loop {
+        write!(
+            out,
+            "Keep which bookmark? [1-{}, empty to skip]: ",
+            names.len()
+        )?;
+        out.flush()?;
+
+        let answer = prompt::read_answer(input)?;
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        match answer.parse::<usize>() {
+            Ok(keep) if keep >= 1 && keep <= names.len() => return Ok(Some(keep - 1)),
+            _ => writeln!(out, "Invalid answer '{}'.", answer)?,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use super::import::FolderMode;
+use super::{Config, ConfigBookmark};
+use std::collections::BTreeMap;
+
+/// Names of the supported export formats.
+pub const FORMATS: &[&str] = &["netscape", "buku", "markdown", "org", "html"];
+
+/// Bookmarks and subfolders of a folder, ordered by name.
+#[derive(Debug, Default)]
+pub struct Folder<'a> {
+    pub bookmarks: Vec<(&'a str, &'a ConfigBookmark)>,
+    pub folders: BTreeMap<String, Folder<'a>>,
+}
+
+impl<'a> Folder<'a> {
+    /// Number of bookmarks in the folder and its subfolders.
+    pub fn len(&self) -> usize {
+        self.bookmarks.len() + self.folders.values().map(Folder::len).sum::<usize>()
+    }
+}
+
+/// Arranges bookmarks in folders.
+///
+/// With `FolderMode::Tags` bookmarks are in the folder of their first tag, with `FolderMode::Path`
+/// in the folders of their `folder` path. Other bookmarks are at the top.
+pub fn folder_tree(config: &Config, folders: FolderMode) -> Folder<'_> {
+    let mut root = Folder::default();
+    for (name, bookmark) in config.bookmarks.iter() {
+        let path: Vec<&str> = match folders {
+            FolderMode::Tags => bookmark.tags.iter().take(1).map(String::as_str).collect(),
+            FolderMode::Path => bookmark
+                .folder
+                .as_deref()
+                .unwrap_or("")
+                .split('/')
+                .map(str::trim)
+                .filter(|folder_name| !folder_name.is_empty())
+                .collect(),
+        };
+
+        let folder = path.into_iter().fold(&mut root, |folder, folder_name| {
+            folder.folders.entry(String::from(folder_name)).or_default()
+        });
+        folder.bookmarks.push((name, bookmark));
+    }
+
+    root
+}
+
+/// Text escaped for HTML content and attribute values.
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
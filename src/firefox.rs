@@ -0,0 +1,193 @@
+use super::import::ImportedBookmark;
+use anyhow::{bail, Context, Result};
+use chrono::TimeZone;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// `moz_bookmarks.type` of bookmarks.
+const TYPE_BOOKMARK: i64 = 1;
+
+/// Parent of the tag folders.
+const TAGS_ROOT_GUID: &str = "tags________";
+
+/// Roots of the bookmark tree, like the menu or the toolbar, which aren't folders of their own.
+const ROOT_GUIDS: &[&str] = &[
+    "root________",
+    "menu________",
+    "toolbar_____",
+    "unfiled_____",
+    "mobile______",
+    TAGS_ROOT_GUID,
+];
+
+/// Reads the bookmarks of a Firefox profile directory or `places.sqlite` database.
+///
+/// Firefox locks the database while it runs, so a copy of it is read. Firefox tags become tags,
+/// and with `keywords` the keywords of bookmarks are added as tags too.
+pub fn read(path: &Path, keywords: bool) -> Result<Vec<ImportedBookmark>> {
+    let database_path = if path.is_dir() {
+        path.join("places.sqlite")
+    } else {
+        path.to_path_buf()
+    };
+    if !database_path.is_file() {
+        bail!(
+            "No Firefox database found at '{}'.",
+            database_path.display()
+        );
+    }
+
+    // The copy is removed whether or not it could be read.
+    let copy_directory = temporary_directory()?;
+    let bookmarks = copy_database(&database_path, &copy_directory).and_then(|copy_path| {
+        read_database(&copy_path, keywords)
+            .with_context(|| format!("Unable to read '{}'.", database_path.display()))
+    });
+    let _ = std::fs::remove_dir_all(&copy_directory);
+
+    bookmarks
+}
+
+/// Copies the database with its write-ahead log, which holds the latest changes.
+fn copy_database(database_path: &Path, copy_directory: &Path) -> Result<std::path::PathBuf> {
+    let copy_path = copy_directory.join("places.sqlite");
+    std::fs::copy(database_path, &copy_path)
+        .with_context(|| format!("Unable to copy '{}'.", database_path.display()))?;
+    let mut wal_path = database_path.as_os_str().to_os_string();
+    wal_path.push("-wal");
+    if Path::new(&wal_path).is_file() {
+        std::fs::copy(&wal_path, copy_directory.join("places.sqlite-wal"))
+            .with_context(|| format!("Unable to copy '{}'.", Path::new(&wal_path).display()))?;
+    }
+
+    Ok(copy_path)
+}
+
+/// Temporary directory for the copy of the database.
+fn temporary_directory() -> Result<std::path::PathBuf> {
+    let directory = std::env::temp_dir().join(format!(
+        "bookit-firefox-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    std::fs::create_dir_all(&directory)?;
+
+    Ok(directory)
+}
+
+/// Item of `moz_bookmarks`.
+struct Item {
+    kind: i64,
+    place: Option<i64>,
+    parent: Option<i64>,
+    title: String,
+    added: Option<i64>,
+    guid: String,
+}
+
+/// Reads the bookmarks, tags and keywords of a places database.
+fn read_database(database_path: &Path, with_keywords: bool) -> Result<Vec<ImportedBookmark>> {
+    let connection = rusqlite::Connection::open_with_flags(
+        database_path,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?;
+
+    // Bookmark tree.
+    let mut statement = connection.prepare(
+        "SELECT id, type, fk, parent, IFNULL(title, ''), dateAdded, IFNULL(guid, '')
+         FROM moz_bookmarks ORDER BY parent, position, id",
+    )?;
+    let mut items: Vec<(i64, Item)> = Vec::new();
+    for row in statement.query_map([], |row| {
+        Ok((
+            row.get(0)?,
+            Item {
+                kind: row.get(1)?,
+                place: row.get(2)?,
+                parent: row.get(3)?,
+                title: row.get(4)?,
+                added: row.get(5)?,
+                guid: row.get(6)?,
+            },
+        ))
+    })? {
+        items.push(row?);
+    }
+    let items_by_id: HashMap<i64, &Item> = items.iter().map(|(id, item)| (*id, item)).collect();
+
+    // Urls.
+    let mut statement = connection.prepare("SELECT id, url FROM moz_places")?;
+    let places: HashMap<i64, String> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    // Keywords.
+    let mut statement = connection.prepare("SELECT place_id, keyword FROM moz_keywords")?;
+    let keywords: HashMap<i64, String> = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<_, _>>()?;
+
+    // Tags are bookmarks of a place inside a folder of the tags root.
+    let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+    for (_, item) in items.iter() {
+        let tag_folder = match item.parent.and_then(|parent| items_by_id.get(&parent)) {
+            Some(tag_folder) if item.kind == TYPE_BOOKMARK => tag_folder,
+            _ => continue,
+        };
+        let is_tag = tag_folder
+            .parent
+            .and_then(|parent| items_by_id.get(&parent))
+            .is_some_and(|tags_root| tags_root.guid == TAGS_ROOT_GUID);
+        if let (true, Some(place)) = (is_tag, item.place) {
+            tags.entry(place)
+                .or_default()
+                .push(tag_folder.title.clone());
+        }
+    }
+
+    // Bookmarks.
+    let mut bookmarks = Vec::new();
+    for (_, item) in items.iter() {
+        let url = match item.place.and_then(|place| places.get(&place)) {
+            Some(url) if item.kind == TYPE_BOOKMARK && !url.starts_with("place:") => url,
+            _ => continue,
+        };
+
+        // Folders from the outermost, skipping the roots.
+        let mut folders = Vec::new();
+        let mut parent = item.parent.and_then(|parent| items_by_id.get(&parent));
+        let mut is_tag = false;
+        while let Some(folder) = parent {
+            if folder.guid == TAGS_ROOT_GUID {
+                is_tag = true;
+            }
+            if !ROOT_GUIDS.contains(&folder.guid.as_str()) {
+                folders.insert(0, folder.title.clone());
+            }
+            parent = folder.parent.and_then(|parent| items_by_id.get(&parent));
+        }
+        if is_tag {
+            continue;
+        }
+
+        let place = item.place.unwrap_or_default();
+        let mut bookmark_tags = tags.get(&place).cloned().unwrap_or_default();
+        if let Some(keyword) = keywords.get(&place).filter(|_| with_keywords) {
+            if !bookmark_tags.contains(keyword) {
+                bookmark_tags.push(keyword.clone());
+            }
+        }
+        bookmarks.push(ImportedBookmark {
+            name: item.title.clone(),
+            url: url.clone(),
+            tags: bookmark_tags,
+            folders,
+            created: item
+                .added
+                .and_then(|microseconds| chrono::Utc.timestamp_micros(microseconds).single()),
+            ..ImportedBookmark::default()
+        });
+    }
+
+    Ok(bookmarks)
+}
@@ -0,0 +1,28 @@
+use anyhow::Result;
+use std::io::Read;
+use std::time::Duration;
+
+/// User agent sent with every request, some sites refuse clients they don't know.
+pub const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/77.0.3865.120 Safari/537.36";
+
+/// Creates an HTTP agent following redirects with a timeout for every request.
+pub fn agent(timeout: Duration) -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(timeout)
+        .user_agent(USER_AGENT)
+        .build()
+}
+
+/// Reads a response body, failing for bodies larger than `limit` bytes.
+pub fn read_body(response: ureq::Response, limit: u64) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(limit + 1)
+        .read_to_end(&mut body)?;
+    if body.len() as u64 > limit {
+        anyhow::bail!("Response is larger than {} bytes.", limit);
+    }
+
+    Ok(body)
+}
@@ -0,0 +1,529 @@
+use super::avatar;
+use super::http;
+use super::index::{Index, IndexEntry};
+use anyhow::{bail, Context, Result};
+use image::{DynamicImage, RgbaImage};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Size in pixels of the icons launchers show.
+pub const ICON_SIZE: u32 = 48;
+
+/// Sizes in pixels of the fixed size directories of the icon theme.
+const ICON_SIZES: &[u32] = &[16, 24, 32, 48, 64, 128];
+
+/// Largest favicon, page or manifest bookit downloads.
+const DOWNLOAD_LIMIT: u64 = 2 * 1024 * 1024;
+
+/// Options of the icon synchronization.
+pub struct IconsOptions {
+    /// Directory of the bookit icon theme.
+    pub icons_path: PathBuf,
+    /// Age after which icons are downloaded again.
+    pub ttl: Duration,
+    /// Download icons regardless of their age.
+    pub force: bool,
+    /// Timeout of every request.
+    pub timeout: Duration,
+}
+
+/// Outcome of synchronizing the icon of a hostname.
+#[derive(Debug, PartialEq)]
+pub enum IconSync {
+    /// The icon was downloaded.
+    Synced,
+    /// A letter avatar was generated.
+    Generated,
+    /// The cached icon is younger than the TTL.
+    Fresh,
+}
+
+/// Downloaded icon before it's written into the icon theme.
+enum IconImage {
+    /// SVG document, kept as the scalable icon.
+    Svg(Vec<u8>),
+    /// Image scaled to every fixed size.
+    Raster(DynamicImage),
+}
+
+/// Directory of the icon theme holding icons of a size, `None` for scalable icons.
+fn theme_dir(size: Option<u32>) -> String {
+    match size {
+        Some(size) => format!("{}x{}/apps", size, size),
+        None => String::from("scalable/apps"),
+    }
+}
+
+/// Every directory of the icon theme.
+fn theme_dirs() -> impl Iterator<Item = Option<u32>> {
+    ICON_SIZES
+        .iter()
+        .copied()
+        .map(Some)
+        .chain(std::iter::once(None))
+}
+
+/// Path of an icon inside the icon theme, see `icon_name`.
+pub fn icon_path(icons_path: &Path, icon_name: &str) -> PathBuf {
+    icon_size_path(icons_path, icon_name, Some(ICON_SIZE))
+}
+
+/// Path of an icon of a size inside the icon theme, `None` for the scalable icon.
+fn icon_size_path(icons_path: &Path, icon_name: &str, size: Option<u32>) -> PathBuf {
+    let extension = if size.is_some() { "png" } else { "svg" };
+    icons_path
+        .join(theme_dir(size))
+        .join(format!("{}.{}", icon_name, extension))
+}
+
+/// Writes the `index.theme` describing the icon theme directories, when it changed.
+pub fn write_theme(icons_path: &Path) -> Result<()> {
+    let directories: Vec<String> = theme_dirs().map(theme_dir).collect();
+    let mut theme = format!(
+        "[Icon Theme]\nName=bookit\nComment=Bookmark icons maintained by bookit\nInherits=hicolor\nDirectories={}\n",
+        directories.join(",")
+    );
+    for size in theme_dirs() {
+        theme.push_str(&format!("\n[{}]\nContext=Applications\n", theme_dir(size)));
+        match size {
+            Some(size) => theme.push_str(&format!("Size={}\nType=Fixed\n", size)),
+            None => theme.push_str("Size=128\nMinSize=8\nMaxSize=512\nType=Scalable\n"),
+        }
+    }
+
+    let theme_path = icons_path.join("index.theme");
+    if std::fs::read_to_string(&theme_path).ok().as_deref() != Some(theme.as_str()) {
+        std::fs::create_dir_all(icons_path)?;
+        std::fs::write(&theme_path, theme)
+            .with_context(|| format!("Unable to save icon theme '{}'.", theme_path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Writes an icon into every directory of the icon theme.
+///
+/// Raster icons remove a scalable icon left from an earlier SVG favicon.
+fn save_icon(icons_path: &Path, icon_name: &str, icon: &IconImage) -> Result<()> {
+    for size in ICON_SIZES {
+        let image = match icon {
+            IconImage::Svg(data) => render_svg(data, *size)?,
+            IconImage::Raster(image) => fit_icon(image, *size),
+        };
+        save_png(&icon_size_path(icons_path, icon_name, Some(*size)), &image)?;
+    }
+
+    let scalable_path = icon_size_path(icons_path, icon_name, None);
+    match icon {
+        IconImage::Svg(data) => {
+            std::fs::create_dir_all(scalable_path.parent().unwrap())?;
+            std::fs::write(&scalable_path, data)
+                .with_context(|| format!("Unable to save icon '{}'.", scalable_path.display()))?;
+        }
+        IconImage::Raster(_) => remove_icon_file(&scalable_path)?,
+    }
+
+    Ok(())
+}
+
+/// Writes an image as a PNG, creating its directory.
+fn save_png(icon_path: &Path, image: &RgbaImage) -> Result<()> {
+    std::fs::create_dir_all(icon_path.parent().unwrap())?;
+    image
+        .save_with_format(icon_path, image::ImageFormat::Png)
+        .with_context(|| format!("Unable to save icon '{}'.", icon_path.display()))
+}
+
+/// Removes an icon file, ignoring missing ones.
+fn remove_icon_file(icon_path: &Path) -> Result<()> {
+    match std::fs::remove_file(icon_path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("Unable to remove icon '{}'.", icon_path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Name of an entry's icon inside the icon theme.
+///
+/// Bookmarks share the icon of their hostname, bookmarks without a hostname that's safe as a file
+/// name get their own.
+pub fn icon_name(entry: &IndexEntry) -> String {
+    match entry.hostname.as_deref().and_then(hostname_icon_name) {
+        Some(icon_name) => icon_name,
+        None => format!("bookmark-{}", slug(&entry.name)),
+    }
+}
+
+/// Icon name of a hostname, `None` unless it's made of `[a-z0-9.-]` without `..`.
+///
+/// The domain of a `mailto:` url can be anything, it must not lead outside the icon theme.
+fn hostname_icon_name(hostname: &str) -> Option<String> {
+    let safe = !hostname.is_empty()
+        && !hostname.starts_with('.')
+        && !hostname.contains("..")
+        && hostname
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.' || c == '-');
+
+    if safe {
+        Some(String::from(hostname))
+    } else {
+        None
+    }
+}
+
+/// Lowercase words of a text joined by `-`, usable in file names.
+pub fn slug(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    words.join("-")
+}
+
+/// Icon of an entry for launchers looking icons up in the icon theme: its override path or name.
+pub fn entry_icon(entry: &IndexEntry) -> String {
+    entry.icon.clone().unwrap_or_else(|| icon_name(entry))
+}
+
+/// Path of an entry's icon: its override path or the path inside the icon theme.
+pub fn entry_icon_path(icons_path: &Path, entry: &IndexEntry) -> PathBuf {
+    match &entry.icon {
+        Some(icon) => PathBuf::from(icon),
+        None => icon_path(icons_path, &icon_name(entry)),
+    }
+}
+
+/// Icons generated offline for bookmarks without a website, by icon name with their initials.
+pub fn fallback_sources(index: &Index) -> BTreeMap<String, String> {
+    let web_sources = icon_sources(index);
+    let mut sources = BTreeMap::new();
+
+    for entry in index.entries() {
+        let icon_name = icon_name(entry);
+        if entry.icon.is_some() || web_sources.contains_key(&icon_name) {
+            continue;
+        }
+
+        let initials = match entry.hostname.as_deref() {
+            Some(hostname) if !hostname.is_empty() => avatar::initials(hostname, true),
+            _ => avatar::initials(&entry.name, false),
+        };
+        sources.entry(icon_name).or_insert(initials);
+    }
+
+    sources
+}
+
+/// Writes a letter avatar as the icon unless one exists, see `avatar::render_avatar`.
+pub fn generate_icon(icon_name: &str, initials: &str, options: &IconsOptions) -> Result<IconSync> {
+    if !options.force && icon_path(&options.icons_path, icon_name).exists() {
+        return Ok(IconSync::Fresh);
+    }
+
+    // Render every size so small icons stay sharp.
+    for size in ICON_SIZES {
+        save_png(
+            &icon_size_path(&options.icons_path, icon_name, Some(*size)),
+            &avatar::render_avatar(icon_name, initials, *size),
+        )?;
+    }
+    remove_icon_file(&icon_size_path(&options.icons_path, icon_name, None))?;
+
+    Ok(IconSync::Generated)
+}
+
+/// Icon files no bookmark references anymore in every theme directory, by icon name.
+pub fn stale_icons(icons_path: &Path, index: &Index) -> Result<BTreeMap<String, Vec<PathBuf>>> {
+    let icon_names: std::collections::BTreeSet<String> =
+        index.entries().iter().map(icon_name).collect();
+
+    let mut stale: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for size in theme_dirs() {
+        let entries = match std::fs::read_dir(icons_path.join(theme_dir(size))) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            if extension != Some("png") && extension != Some("svg") {
+                continue;
+            }
+            let icon_name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(icon_name) => String::from(icon_name),
+                None => continue,
+            };
+
+            if !icon_names.contains(&icon_name) {
+                stale.entry(icon_name).or_default().push(path);
+            }
+        }
+    }
+
+    Ok(stale)
+}
+
+/// Hostnames of the web bookmarks with the url of a page to discover their favicon on.
+pub fn icon_sources(index: &Index) -> BTreeMap<String, url::Url> {
+    let mut sources = BTreeMap::new();
+
+    for entry in index.entries() {
+        let hostname = match entry.hostname.as_deref().and_then(hostname_icon_name) {
+            Some(hostname) => hostname,
+            None => continue,
+        };
+        let page_url = match url::Url::parse(&entry.url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+            _ => continue,
+        };
+
+        sources
+            .entry(hostname)
+            .or_insert_with(|| page_url.join("/").unwrap_or(page_url));
+    }
+
+    sources
+}
+
+/// Downloads the favicon of a hostname into the icon theme unless the cached one is fresh.
+pub fn sync_icon(
+    agent: &ureq::Agent,
+    hostname: &str,
+    page_url: &url::Url,
+    options: &IconsOptions,
+) -> Result<IconSync> {
+    // Check icon cache.
+    let icon_path = icon_path(&options.icons_path, hostname);
+    if !options.force && is_fresh(&icon_path, options.ttl) {
+        return Ok(IconSync::Fresh);
+    }
+
+    // Download the first favicon that can be decoded.
+    let mut errors = Vec::new();
+    for favicon_url in discover_favicons(agent, page_url) {
+        log::debug!("Trying favicon '{}'.", favicon_url);
+        match download_icon(agent, &favicon_url) {
+            Ok(icon) => {
+                save_icon(&options.icons_path, hostname, &icon)?;
+
+                return Ok(IconSync::Synced);
+            }
+            Err(e) => errors.push(format!("{}: {}", favicon_url, e)),
+        }
+    }
+
+    bail!("No favicon found ({}).", errors.join(", "))
+}
+
+/// Whether a file was modified within the TTL.
+fn is_fresh(path: &Path, ttl: Duration) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| {
+            SystemTime::now()
+                .duration_since(modified)
+                .map(|age| age < ttl)
+                .unwrap_or(true)
+        })
+        .unwrap_or(false)
+}
+
+/// Favicon candidates of a page, best first, ending with `/favicon.ico`.
+fn discover_favicons(agent: &ureq::Agent, page_url: &url::Url) -> Vec<url::Url> {
+    // (score, url) where a higher score is a better candidate.
+    let mut candidates: Vec<(u32, url::Url)> = Vec::new();
+
+    match fetch_html(agent, page_url) {
+        Ok((base_url, html)) => {
+            let document = scraper::Html::parse_document(&html);
+            let links = scraper::Selector::parse("link[rel][href]").unwrap();
+
+            for link in document.select(&links) {
+                let rel = link.value().attr("rel").unwrap_or("").to_lowercase();
+                let href = link.value().attr("href").unwrap_or("");
+                let href_url = match base_url.join(href) {
+                    Ok(href_url) => href_url,
+                    Err(_) => continue,
+                };
+
+                if rel.split_whitespace().any(|rel| rel == "manifest") {
+                    candidates.extend(manifest_icons(agent, &href_url));
+                } else if rel.split_whitespace().any(|rel| rel.ends_with("icon")) {
+                    let score = icon_score(
+                        link.value().attr("sizes"),
+                        link.value().attr("type"),
+                        &href_url,
+                    );
+                    candidates.push((score, href_url));
+                }
+            }
+        }
+        Err(e) => log::debug!("Unable to fetch '{}': {}", page_url, e),
+    }
+
+    // Stable so equal candidates keep document order.
+    candidates.sort_by_key(|candidate| std::cmp::Reverse(candidate.0));
+    let mut favicons: Vec<url::Url> = Vec::new();
+    for (_, candidate) in candidates {
+        if !favicons.contains(&candidate) {
+            favicons.push(candidate);
+        }
+    }
+    if let Ok(favicon_ico) = page_url.join("/favicon.ico") {
+        if !favicons.contains(&favicon_ico) {
+            favicons.push(favicon_ico);
+        }
+    }
+
+    favicons
+}
+
+/// Icons listed in a web app manifest.
+fn manifest_icons(agent: &ureq::Agent, manifest_url: &url::Url) -> Vec<(u32, url::Url)> {
+    #[derive(serde::Deserialize)]
+    struct Manifest {
+        #[serde(default)]
+        icons: Vec<ManifestIcon>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct ManifestIcon {
+        src: String,
+        sizes: Option<String>,
+        #[serde(rename = "type")]
+        type_: Option<String>,
+    }
+
+    let manifest: Manifest = match agent
+        .get(manifest_url.as_str())
+        .call()
+        .map_err(anyhow::Error::from)
+        .and_then(|response| http::read_body(response, DOWNLOAD_LIMIT))
+        .and_then(|body| Ok(serde_json::from_slice(&body)?))
+    {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            log::debug!("Unable to read manifest '{}': {}", manifest_url, e);
+            return Vec::new();
+        }
+    };
+
+    manifest
+        .icons
+        .into_iter()
+        .filter_map(|icon| {
+            let src_url = manifest_url.join(&icon.src).ok()?;
+            let score = icon_score(icon.sizes.as_deref(), icon.type_.as_deref(), &src_url);
+            Some((score, src_url))
+        })
+        .collect()
+}
+
+/// Scores an icon by how well it scales to the largest size of the icon theme.
+fn icon_score(sizes: Option<&str>, type_: Option<&str>, url: &url::Url) -> u32 {
+    // Vector icons render sharp at every size.
+    if type_ == Some("image/svg+xml") || url.path().ends_with(".svg") {
+        return u32::MAX;
+    }
+
+    // Prefer the smallest icon at least as large as the largest size, then the largest one.
+    let largest_size = *ICON_SIZES.last().unwrap();
+    let size = sizes
+        .unwrap_or("")
+        .split_whitespace()
+        .filter_map(|size| size.split(['x', 'X']).next()?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    if size >= largest_size {
+        u32::MAX - 1 - size
+    } else {
+        size
+    }
+}
+
+/// Fetches an HTML page, returning the url it was served from after redirects.
+fn fetch_html(agent: &ureq::Agent, page_url: &url::Url) -> Result<(url::Url, String)> {
+    let response = agent.get(page_url.as_str()).call()?;
+    let base_url = url::Url::parse(response.get_url())?;
+    let body = http::read_body(response, DOWNLOAD_LIMIT)?;
+
+    Ok((base_url, String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// Downloads and decodes an icon.
+fn download_icon(agent: &ureq::Agent, favicon_url: &url::Url) -> Result<IconImage> {
+    let response = agent.get(favicon_url.as_str()).call()?;
+    let content_type = String::from(response.content_type());
+    let body = http::read_body(response, DOWNLOAD_LIMIT)?;
+
+    decode_icon(&body, &content_type, favicon_url.path())
+}
+
+/// Decodes ICO, PNG, JPEG, GIF, WebP, BMP or SVG data.
+fn decode_icon(data: &[u8], content_type: &str, path: &str) -> Result<IconImage> {
+    if is_svg(data, content_type, path) {
+        // Only keep SVG documents that render.
+        resvg::usvg::Tree::from_data(data, &resvg::usvg::Options::default())
+            .context("Invalid SVG icon.")?;
+        return Ok(IconImage::Svg(data.to_vec()));
+    }
+
+    Ok(IconImage::Raster(
+        image::load_from_memory(data).context("Unsupported icon format.")?,
+    ))
+}
+
+/// Scales an image into a square icon, centering images that aren't square.
+fn fit_icon(image: &DynamicImage, size: u32) -> RgbaImage {
+    let image = image.resize(size, size, image::imageops::FilterType::Lanczos3);
+
+    let mut icon = RgbaImage::new(size, size);
+    image::imageops::overlay(
+        &mut icon,
+        &image.to_rgba8(),
+        i64::from((size - image.width()) / 2),
+        i64::from((size - image.height()) / 2),
+    );
+
+    icon
+}
+
+/// Whether icon data is an SVG document.
+fn is_svg(data: &[u8], content_type: &str, path: &str) -> bool {
+    if content_type.contains("svg") || path.ends_with(".svg") {
+        return true;
+    }
+
+    let start = String::from_utf8_lossy(&data[..data.len().min(256)]).to_lowercase();
+    !content_type.contains("html") && start.contains("<svg")
+}
+
+/// Renders an SVG document centered into a square image.
+fn render_svg(data: &[u8], size: u32) -> Result<RgbaImage> {
+    let tree = resvg::usvg::Tree::from_data(data, &resvg::usvg::Options::default())
+        .context("Invalid SVG icon.")?;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size, size).unwrap();
+
+    let tree_size = tree.size();
+    let scale = size as f32 / tree_size.width().max(tree_size.height());
+    let transform = resvg::tiny_skia::Transform::from_translate(
+        (size as f32 - tree_size.width() * scale) / 2.0,
+        (size as f32 - tree_size.height() * scale) / 2.0,
+    )
+    .pre_scale(scale, scale);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia stores premultiplied colors.
+    let mut icon = RgbaImage::new(size, size);
+    for (pixel, color) in icon.pixels_mut().zip(pixmap.pixels()) {
+        let color = color.demultiply();
+        *pixel = image::Rgba([color.red(), color.green(), color.blue(), color.alpha()]);
+    }
+
+    Ok(icon)
+}
@@ -0,0 +1,202 @@
+use super::{icons, load_config, save_config, urls, ConfigBookmark};
+use anyhow::{bail, Result};
+use chrono::SubsecRound;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+
+/// Names of the supported import formats.
+pub const FORMATS: &[&str] = &[
+    "netscape", "firefox", "chromium", "buku", "csv", "json", "markdown", "org", "text",
+];
+
+/// Names of the supported folder mappings.
+pub const FOLDER_MODES: &[&str] = &["tags", "path"];
+
+/// Names of the supported name conflict policies.
+pub const CONFLICT_POLICIES: &[&str] = &["skip", "rename", "overwrite"];
+
+/// How folders of imported bookmarks are kept.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FolderMode {
+    /// Every folder becomes a tag.
+    Tags,
+    /// The folder path is stored in the bookmark's `folder`.
+    Path,
+}
+
+impl std::str::FromStr for FolderMode {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Ok(match name {
+            "tags" => FolderMode::Tags,
+            "path" => FolderMode::Path,
+            _ => bail!("Folder mode '{}' not supported.", name),
+        })
+    }
+}
+
+/// What happens to imported bookmarks whose name is taken by a bookmark of another url.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+    /// Keep the existing bookmark.
+    Skip,
+    /// Import under the name with a number, like `name (2)`.
+    Rename,
+    /// Replace the existing bookmark.
+    Overwrite,
+}
+
+impl std::str::FromStr for ConflictPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Ok(match name {
+            "skip" => ConflictPolicy::Skip,
+            "rename" => ConflictPolicy::Rename,
+            "overwrite" => ConflictPolicy::Overwrite,
+            _ => bail!("Conflict policy '{}' not supported.", name),
+        })
+    }
+}
+
+/// Options of the import command.
+pub struct ImportOptions {
+    pub folders: FolderMode,
+    pub conflict: ConflictPolicy,
+    /// Only list the bookmarks that would be imported.
+    pub dry_run: bool,
+}
+
+/// Bookmark read from another program.
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportedBookmark {
+    /// Title of the bookmark, the url is used when it's empty.
+    pub name: String,
+    pub url: String,
+    pub tags: Vec<String>,
+    /// Folders from the outermost to the innermost.
+    pub folders: Vec<String>,
+    pub description: Option<String>,
+    pub created: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Adds imported bookmarks to the configuration and prints a summary.
+///
+/// Bookmarks whose url is already bookmarked are skipped, so importing the same file again adds
+/// nothing. A dry run lists the bookmarks it would import instead of saving them.
+pub fn run(
+    config_path: &Path,
+    bookmarks: Vec<ImportedBookmark>,
+    options: &ImportOptions,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let mut config = load_config(config_path)?;
+    let normalize_options = urls::NormalizeOptions::from_env();
+    let mut known_urls: HashSet<String> = config
+        .bookmarks
+        .values()
+        .map(|bookmark| urls::duplicate_key(&bookmark.url, &normalize_options))
+        .collect();
+
+    let (mut imported, mut existing, mut renamed, mut overwritten, mut skipped) = (0, 0, 0, 0, 0);
+    for bookmark in bookmarks {
+        // Skip urls that are bookmarked already.
+        let (url, _) = urls::normalize(&bookmark.url, &normalize_options);
+        if !known_urls.insert(urls::duplicate_key(&url, &normalize_options)) {
+            existing += 1;
+            continue;
+        }
+
+        // Resolve name conflicts.
+        let mut name = String::from(bookmark.name.trim());
+        if name.is_empty() {
+            name = url.clone();
+        }
+        if config.bookmarks.contains_key(&name) {
+            match options.conflict {
+                ConflictPolicy::Skip => {
+                    log::debug!("Skipped bookmark '{}', the name is taken.", name);
+                    skipped += 1;
+                    continue;
+                }
+                ConflictPolicy::Rename => {
+                    name = (2..)
+                        .map(|number| format!("{} ({})", name, number))
+                        .find(|numbered_name| !config.bookmarks.contains_key(numbered_name))
+                        .unwrap();
+                    renamed += 1;
+                }
+                ConflictPolicy::Overwrite => overwritten += 1,
+            }
+        }
+
+        // Map folders.
+        let mut tags = bookmark.tags;
+        let mut folder = None;
+        match options.folders {
+            FolderMode::Tags => {
+                for folder_name in bookmark.folders.iter() {
+                    let tag = icons::slug(folder_name);
+                    if !tag.is_empty() && !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                }
+            }
+            FolderMode::Path if !bookmark.folders.is_empty() => {
+                folder = Some(bookmark.folders.join("/"));
+            }
+            FolderMode::Path => {}
+        }
+
+        if options.dry_run {
+            write!(out, "Would import bookmark '{}' from '{}'", name, url)?;
+            if !tags.is_empty() {
+                write!(out, " with tags '{}'", tags.join(","))?;
+            }
+            if let Some(folder) = &folder {
+                write!(out, " into '{}'", folder)?;
+            }
+            writeln!(out, ".")?;
+        }
+        config.bookmarks.insert(
+            name,
+            ConfigBookmark {
+                url,
+                tags,
+                folder,
+                description: bookmark.description,
+                created: Some(
+                    bookmark
+                        .created
+                        .unwrap_or_else(chrono::Utc::now)
+                        .trunc_subsecs(0),
+                ),
+                ..ConfigBookmark::default()
+            },
+        );
+        imported += 1;
+    }
+
+    // Save.
+    if !options.dry_run && imported > 0 {
+        save_config(config_path, &config)?;
+    }
+    writeln!(
+        out,
+        "{} {} bookmarks, {} already bookmarked, {} renamed, {} overwritten, {} skipped.",
+        if options.dry_run {
+            "Would import"
+        } else {
+            "Imported"
+        },
+        imported,
+        existing,
+        renamed,
+        overwritten,
+        skipped
+    )?;
+
+    Ok(())
+}
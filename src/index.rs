@@ -0,0 +1,250 @@
+use super::{urls, Config};
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Version of the index format, bumped whenever `Index` changes shape.
+const INDEX_VERSION: u32 = 4;
+
+/// Cached view of a bookit configuration file.
+///
+/// The index is stored next to the configuration file, or at `$BOOKIT_INDEX_PATH`, and rebuilt
+/// whenever the configuration file changes, so listing, searching and tag listing never have to
+/// parse the YAML.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Index {
+    version: u32,
+    stamp: IndexStamp,
+    entries: Vec<IndexEntry>,
+    tags: BTreeMap<String, Vec<usize>>,
+    tokens: BTreeMap<String, Vec<usize>>,
+}
+
+/// Identifies the configuration file contents an index was built from.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct IndexStamp {
+    /// Configuration file, an index path set in the environment can be shared by several.
+    config_path: PathBuf,
+    modified: u128,
+    length: u64,
+    hash: u64,
+}
+
+/// Bookmark as stored in the index.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct IndexEntry {
+    pub name: String,
+    pub url: String,
+    pub tags: Vec<String>,
+    /// Scheme of the url, `None` when the url can't be parsed.
+    pub scheme: Option<String>,
+    pub hostname: Option<String>,
+    /// Icon path overriding the icon of the hostname.
+    pub icon: Option<String>,
+}
+
+impl Index {
+    /// Builds an index from a parsed configuration.
+    fn build(config: &Config, stamp: IndexStamp) -> Index {
+        let mut index = Index {
+            version: INDEX_VERSION,
+            stamp,
+            entries: Vec::with_capacity(config.bookmarks.len()),
+            tags: BTreeMap::new(),
+            tokens: BTreeMap::new(),
+        };
+
+        for (position, (name, bookmark)) in config.bookmarks.iter().enumerate() {
+            let url = urls::parse(&bookmark.url).ok();
+
+            // Tags.
+            for tag in bookmark.tags.iter() {
+                index.tags.entry(tag.clone()).or_default().push(position);
+            }
+
+            // Search tokens.
+            let mut tokens = BTreeSet::new();
+            tokens.extend(tokenize(name));
+            tokens.extend(tokenize(&bookmark.url));
+            for tag in bookmark.tags.iter() {
+                tokens.extend(tokenize(tag));
+            }
+            for token in tokens {
+                index.tokens.entry(token).or_default().push(position);
+            }
+
+            index.entries.push(IndexEntry {
+                name: name.clone(),
+                url: bookmark.url.clone(),
+                tags: bookmark.tags.clone(),
+                scheme: url.as_ref().map(|url| String::from(url.scheme())),
+                hostname: url.as_ref().and_then(urls::hostname),
+                icon: bookmark
+                    .icon
+                    .as_ref()
+                    .map(|icon| shellexpand::tilde(icon).into_owned()),
+            });
+        }
+
+        index
+    }
+
+    /// All bookmarks ordered by name.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// All tags with the number of bookmarks using them, ordered by tag.
+    pub fn tags(&self) -> impl Iterator<Item = (&String, usize)> {
+        self.tags
+            .iter()
+            .map(|(tag, positions)| (tag, positions.len()))
+    }
+
+    /// Bookmarks where every term of the query prefixes a token of the name, url or tags.
+    pub fn search(&self, query: &str) -> Vec<&IndexEntry> {
+        let mut matches: Option<BTreeSet<usize>> = None;
+
+        for term in tokenize(query) {
+            let term_matches: BTreeSet<usize> = self
+                .tokens
+                .range(term.clone()..)
+                .take_while(|(token, _)| token.starts_with(&term))
+                .flat_map(|(_, positions)| positions.iter().copied())
+                .collect();
+
+            matches = Some(match matches {
+                Some(matches) => matches.intersection(&term_matches).copied().collect(),
+                None => term_matches,
+            });
+        }
+
+        matches
+            .unwrap_or_default()
+            .into_iter()
+            .map(|position| &self.entries[position])
+            .collect()
+    }
+}
+
+/// Path of the index for a configuration file, `None` when `$BOOKIT_INDEX_PATH` is empty.
+pub fn index_path(config_path: &Path) -> Option<PathBuf> {
+    match std::env::var_os("BOOKIT_INDEX_PATH") {
+        Some(index_path) if index_path.is_empty() => None,
+        Some(index_path) => Some(PathBuf::from(
+            shellexpand::tilde(&index_path.to_string_lossy()).into_owned(),
+        )),
+        None => {
+            let mut index_path = config_path.as_os_str().to_owned();
+            index_path.push(".index");
+            Some(PathBuf::from(index_path))
+        }
+    }
+}
+
+/// Loads the index for a configuration file, rebuilding it when the configuration changed.
+pub fn load_index(config_path: &Path) -> Result<Index> {
+    let metadata = std::fs::metadata(config_path)
+        .with_context(|| format!("No config found at '{}'.", config_path.display()))?;
+    let modified = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    let length = metadata.len();
+
+    // Use the stored index when the configuration file wasn't touched.
+    let index_path = index_path(config_path);
+    let stored_index = match index_path.as_deref().and_then(read_index) {
+        Some(index)
+            if index.stamp.config_path == config_path
+                && index.stamp.modified == modified
+                && index.stamp.length == length =>
+        {
+            return Ok(index);
+        }
+        stored_index => stored_index,
+    };
+
+    // Otherwise compare the contents.
+    let contents = std::fs::read_to_string(config_path)
+        .with_context(|| format!("No config found at '{}'.", config_path.display()))?;
+    let stamp = IndexStamp {
+        config_path: PathBuf::from(config_path),
+        modified,
+        length,
+        hash: hash(contents.as_bytes()),
+    };
+    let index = match stored_index {
+        Some(mut index)
+            if index.stamp.config_path == stamp.config_path && index.stamp.hash == stamp.hash =>
+        {
+            index.stamp = stamp;
+            index
+        }
+        _ => {
+            log::debug!("Rebuilding index of '{}'.", config_path.display());
+            let config: Config = serde_yaml::from_str(&contents)?;
+            Index::build(&config, stamp)
+        }
+    };
+
+    // An index that can't be saved only costs performance, stdout is the launcher's input.
+    if let Some(index_path) = index_path {
+        if let Err(e) = write_index(&index_path, &index) {
+            eprintln!("Unable to save index '{}': {}", index_path.display(), e);
+        }
+    }
+
+    Ok(index)
+}
+
+/// Reads a stored index, ignoring missing, corrupt or outdated ones.
+fn read_index(index_path: &Path) -> Option<Index> {
+    let contents = std::fs::read(index_path).ok()?;
+    let index: Index = bincode::deserialize(&contents).ok()?;
+    if index.version != INDEX_VERSION {
+        return None;
+    }
+
+    Some(index)
+}
+
+/// Writes an index atomically so concurrent readers never see a partial file.
+///
+/// Every writer uses a temporary file of its own, which is removed when the index can't be saved.
+fn write_index(index_path: &Path, index: &Index) -> Result<()> {
+    let mut index_temporary_path = index_path.as_os_str().to_owned();
+    index_temporary_path.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+
+    let write = || -> Result<()> {
+        std::fs::write(&index_temporary_path, bincode::serialize(index)?)?;
+        std::fs::rename(&index_temporary_path, index_path)?;
+
+        Ok(())
+    };
+    let result = write();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&index_temporary_path);
+    }
+
+    result
+}
+
+/// Splits text into lowercase alphanumeric search tokens.
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_lowercase())
+}
+
+/// FNV-1a hash, stable across builds unlike the standard library hasher.
+pub fn hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
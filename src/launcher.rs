@@ -0,0 +1,229 @@
+use super::icons;
+use super::index::IndexEntry;
+use super::urls;
+use anyhow::{bail, Result};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Names of the supported launcher output formats.
+pub const LAUNCHERS: &[&str] = &[
+    "rofi",
+    "alfred",
+    "ulauncher",
+    "albert",
+    "fzf",
+    "wofi",
+    "tofi",
+    "dmenu",
+];
+
+/// Output format for a launcher program.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Launcher {
+    /// Tab separated rows with rofi's `\0icon\x1f` row option.
+    Rofi,
+    /// Alfred script filter JSON.
+    Alfred,
+    /// JSON array of items as understood by Ulauncher and Albert extensions.
+    Ulauncher,
+    /// Tab separated rows with hostname and icon fields for `--preview`.
+    Fzf,
+    /// dmenu rows with wofi's `img:<path>:text:<label>` image prefix.
+    Wofi,
+    /// Plain dmenu rows for tofi, dmenu, bemenu and friends.
+    Dmenu,
+}
+
+impl std::str::FromStr for Launcher {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Ok(match name {
+            "rofi" => Launcher::Rofi,
+            "alfred" => Launcher::Alfred,
+            "ulauncher" | "albert" => Launcher::Ulauncher,
+            "fzf" => Launcher::Fzf,
+            "wofi" => Launcher::Wofi,
+            "tofi" | "dmenu" => Launcher::Dmenu,
+            _ => bail!("Launcher '{}' not supported.", name),
+        })
+    }
+}
+
+/// Options shared by every launcher output format.
+pub struct LauncherOptions {
+    /// Include icons in the output.
+    pub include_icon: bool,
+    /// Directory of the bookit icon theme.
+    pub icons_path: PathBuf,
+}
+
+impl LauncherOptions {
+    /// Path to the icon of an entry.
+    fn icon_path(&self, entry: &IndexEntry) -> PathBuf {
+        icons::entry_icon_path(&self.icons_path, entry)
+    }
+}
+
+#[derive(serde::Serialize)]
+struct AlfredItems<'a> {
+    items: Vec<AlfredItem<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct AlfredItem<'a> {
+    uid: &'a str,
+    title: &'a str,
+    subtitle: String,
+    arg: &'a str,
+    autocomplete: &'a str,
+    #[serde(rename = "match")]
+    match_: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<AlfredIcon>,
+}
+
+#[derive(serde::Serialize)]
+struct AlfredIcon {
+    path: PathBuf,
+}
+
+#[derive(serde::Serialize)]
+struct UlauncherItem<'a> {
+    name: &'a str,
+    description: String,
+    url: &'a str,
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<PathBuf>,
+}
+
+impl Launcher {
+    /// Writes bookmark entries in the launcher's format.
+    pub fn write_entries(
+        &self,
+        out: &mut dyn Write,
+        entries: &[&IndexEntry],
+        options: &LauncherOptions,
+    ) -> Result<()> {
+        warn_invalid(entries);
+
+        match self {
+            Launcher::Rofi => {
+                for entry in entries {
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}{}",
+                        entry.name,
+                        entry.tags.join(","),
+                        entry.url,
+                        if options.include_icon {
+                            format!("\t\0icon\x1f{}", icons::entry_icon(entry))
+                        } else {
+                            String::from("")
+                        },
+                    )?;
+                }
+            }
+            Launcher::Alfred => {
+                let mut items = Vec::with_capacity(entries.len());
+                for entry in entries {
+                    let hostname = entry.hostname.as_deref().unwrap_or("");
+                    items.push(AlfredItem {
+                        uid: &entry.name,
+                        title: &entry.name,
+                        subtitle: urls::display(&entry.url),
+                        arg: &entry.url,
+                        autocomplete: &entry.name,
+                        match_: format!("{} {} {}", entry.name, entry.tags.join(" "), hostname),
+                        icon: if options.include_icon {
+                            Some(AlfredIcon {
+                                path: options.icon_path(entry),
+                            })
+                        } else {
+                            None
+                        },
+                    });
+                }
+                serde_json::to_writer(&mut *out, &AlfredItems { items })?;
+                writeln!(out)?;
+            }
+            Launcher::Ulauncher => {
+                let mut items = Vec::with_capacity(entries.len());
+                for entry in entries {
+                    items.push(UlauncherItem {
+                        name: &entry.name,
+                        description: urls::display(&entry.url),
+                        url: &entry.url,
+                        tags: &entry.tags,
+                        icon: if options.include_icon {
+                            Some(options.icon_path(entry))
+                        } else {
+                            None
+                        },
+                    });
+                }
+                serde_json::to_writer(&mut *out, &items)?;
+                writeln!(out)?;
+            }
+            Launcher::Fzf => {
+                for entry in entries {
+                    let hostname = entry.hostname.as_deref().unwrap_or("");
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}\t{}{}",
+                        entry.name,
+                        entry.tags.join(","),
+                        entry.url,
+                        hostname,
+                        if options.include_icon {
+                            format!("\t{}", options.icon_path(entry).display())
+                        } else {
+                            String::from("")
+                        },
+                    )?;
+                }
+            }
+            Launcher::Wofi => {
+                for entry in entries {
+                    writeln!(
+                        out,
+                        "{}{}\t{}\t{}",
+                        if options.include_icon {
+                            format!("img:{}:text:", options.icon_path(entry).display())
+                        } else {
+                            String::from("")
+                        },
+                        entry.name,
+                        entry.tags.join(","),
+                        entry.url,
+                    )?;
+                }
+            }
+            Launcher::Dmenu => {
+                for entry in entries {
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}",
+                        entry.name,
+                        entry.tags.join(","),
+                        entry.url,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Warns about entries whose url can't be parsed, they are listed without a hostname.
+fn warn_invalid(entries: &[&IndexEntry]) {
+    for entry in entries.iter().filter(|entry| entry.scheme.is_none()) {
+        // stdout is the launcher's input.
+        eprintln!(
+            "Cannot parse url '{}' of bookmark '{}'.",
+            entry.url, entry.name
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use super::{http, load_config, prompt, save_config, urls, ConfigLinkCheck, ConfigUrlChange};
+use anyhow::Result;
+use chrono::SubsecRound;
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Redirects followed before a link counts as broken.
+const MAX_REDIRECTS: usize = 10;
+
+/// Options of the link checker.
+pub struct LinksOptions {
+    /// Time to wait for every request.
+    pub timeout: Duration,
+    /// Number of links checked at the same time.
+    pub jobs: usize,
+    /// Minimum time between two requests to the same host.
+    pub delay: Duration,
+    /// Tag added to broken bookmarks and removed from working ones.
+    pub tag: Option<String>,
+    /// Update the urls of permanently redirected bookmarks.
+    pub fix_redirects: bool,
+    /// Update urls without asking.
+    pub yes: bool,
+}
+
+/// Outcome of checking a link.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkState {
+    /// The link answered with a success status without redirects.
+    Ok,
+    /// The link redirects to another url that answered with a success status.
+    Redirected {
+        /// Whether every redirect was permanent.
+        permanent: bool,
+    },
+    /// The link answered with an error status or the connection failed.
+    Broken,
+    /// The link didn't answer in time.
+    TimedOut,
+}
+
+/// Result of checking a link.
+#[derive(Debug, Clone)]
+pub struct LinkCheck {
+    pub state: LinkState,
+    /// Status of the last response.
+    pub status: Option<u16>,
+    /// Url of the last response.
+    pub final_url: String,
+    /// Why the link couldn't be checked.
+    pub error: Option<String>,
+}
+
+impl LinkCheck {
+    /// Link check as recorded on the bookmark.
+    pub fn to_config(&self, url: &str) -> ConfigLinkCheck {
+        ConfigLinkCheck {
+            checked: chrono::Utc::now().trunc_subsecs(0),
+            status: self.status,
+            url: if self.final_url != url {
+                Some(self.final_url.clone())
+            } else {
+                None
+            },
+            error: self.error.clone(),
+        }
+    }
+}
+
+/// Checks the links of all web bookmarks, records the results and prints the problems.
+///
+/// Permanently redirected bookmarks can be updated to the url they redirect to, keeping the old
+/// url in their history.
+pub fn run(
+    config_path: &Path,
+    options: &LinksOptions,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let mut config = load_config(config_path)?;
+
+    // Check.
+    let links: Vec<(String, String)> = config
+        .bookmarks
+        .iter()
+        .filter(|(_, bookmark)| is_web_url(&bookmark.url))
+        .map(|(name, bookmark)| (name.clone(), bookmark.url.clone()))
+        .collect();
+    let checks = check_links(&links, options);
+
+    // Report.
+    let normalize_options = urls::NormalizeOptions::from_env();
+    let (mut ok, mut redirected, mut broken, mut timed_out) = (0, 0, 0, 0);
+    let mut updated = 0;
+    for (name, check) in checks.iter() {
+        let bookmark = config.bookmarks.get_mut(name).unwrap();
+        let checked_url = bookmark.url.clone();
+        match check.state {
+            LinkState::Ok => ok += 1,
+            LinkState::Redirected { permanent } => {
+                writeln!(
+                    out,
+                    "Bookmark '{}' redirects from '{}' to '{}'.",
+                    name, bookmark.url, check.final_url
+                )?;
+                redirected += 1;
+
+                let (final_url, _) = urls::normalize(&check.final_url, &normalize_options);
+                if permanent
+                    && options.fix_redirects
+                    && (options.yes
+                        || prompt::confirm(
+                            input,
+                            out,
+                            &format!("Update bookmark '{}' to '{}'?", name, final_url),
+                        )?)
+                {
+                    let old_url = std::mem::replace(&mut bookmark.url, final_url);
+                    bookmark.history.push(ConfigUrlChange {
+                        url: old_url,
+                        changed: chrono::Utc::now().trunc_subsecs(0),
+                    });
+                    writeln!(out, "Updated bookmark '{}' to '{}'.", name, bookmark.url)?;
+                    updated += 1;
+                }
+            }
+            LinkState::Broken => {
+                match (check.status, &check.error) {
+                    (Some(status), _) => writeln!(
+                        out,
+                        "Bookmark '{}' is broken ({}): '{}'.",
+                        name, status, check.final_url
+                    )?,
+                    (None, error) => writeln!(
+                        out,
+                        "Bookmark '{}' is broken: {}",
+                        name,
+                        error.as_deref().unwrap_or("")
+                    )?,
+                }
+                broken += 1;
+            }
+            LinkState::TimedOut => {
+                writeln!(out, "Bookmark '{}' timed out: '{}'.", name, bookmark.url)?;
+                timed_out += 1;
+            }
+        }
+
+        // Tag broken links, timeouts may be temporary so they keep their tags.
+        if let Some(tag) = &options.tag {
+            match check.state {
+                LinkState::Broken if !bookmark.tags.contains(tag) => {
+                    bookmark.tags.push(tag.clone())
+                }
+                LinkState::Ok | LinkState::Redirected { .. } => {
+                    bookmark.tags.retain(|bookmark_tag| bookmark_tag != tag)
+                }
+                _ => {}
+            }
+        }
+
+        // The check describes the url that was checked, even when it was just updated.
+        bookmark.check = Some(check.to_config(&checked_url));
+    }
+
+    // Save.
+    if !checks.is_empty() {
+        save_config(config_path, &config)?;
+    }
+    writeln!(
+        out,
+        "Checked {} bookmarks, {} ok, {} redirected, {} broken, {} timed out.",
+        checks.len(),
+        ok,
+        redirected,
+        broken,
+        timed_out
+    )?;
+    if options.fix_redirects {
+        writeln!(out, "Updated {} bookmarks.", updated)?;
+    }
+
+    Ok(())
+}
+
+/// Whether a url can be checked with HTTP requests.
+fn is_web_url(url: &str) -> bool {
+    urls::parse(url)
+        .map(|url| url.scheme() == "http" || url.scheme() == "https")
+        .unwrap_or(false)
+}
+
+/// Checks links of `(name, url)` concurrently, returning the results by name.
+pub fn check_links(
+    links: &[(String, String)],
+    options: &LinksOptions,
+) -> BTreeMap<String, LinkCheck> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(options.timeout)
+        .user_agent(http::USER_AGENT)
+        .redirects(0)
+        .build();
+    let limiter = RateLimiter::new(options.delay);
+    let queue = Mutex::new(links.iter());
+    let checks = Mutex::new(BTreeMap::new());
+
+    std::thread::scope(|scope| {
+        for _ in 0..options.jobs.max(1) {
+            scope.spawn(|| loop {
+                let next = queue.lock().unwrap().next();
+                let (name, url) = match next {
+                    Some(link) => link,
+                    None => break,
+                };
+
+                log::debug!("Checking '{}'.", url);
+                let check = check_link(&agent, &limiter, url);
+                checks.lock().unwrap().insert(name.clone(), check);
+            });
+        }
+    });
+
+    checks.into_inner().unwrap()
+}
+
+/// Checks a link, following redirects by hand to know whether they're permanent.
+fn check_link(agent: &ureq::Agent, limiter: &RateLimiter, url: &str) -> LinkCheck {
+    let mut current_url = match urls::parse(url) {
+        Ok(url) => url,
+        Err(e) => return failed_check(url, LinkState::Broken, e.to_string()),
+    };
+    let mut permanent = true;
+
+    for redirects in 0..=MAX_REDIRECTS {
+        limiter.wait(current_url.host_str().unwrap_or(""));
+        let response = match request_status(agent, &current_url) {
+            Ok(response) => response,
+            Err(e) if is_timeout(&e) => {
+                return failed_check(
+                    current_url.as_str(),
+                    LinkState::TimedOut,
+                    String::from("Timed out."),
+                )
+            }
+            Err(e) => return failed_check(current_url.as_str(), LinkState::Broken, e.to_string()),
+        };
+
+        let location = match response {
+            (300..=399, Some(location)) => location,
+            (status, _) => {
+                let state = match status {
+                    200..=399 if redirects == 0 => LinkState::Ok,
+                    200..=399 => LinkState::Redirected { permanent },
+                    _ => LinkState::Broken,
+                };
+
+                return LinkCheck {
+                    state,
+                    status: Some(status),
+                    final_url: String::from(current_url),
+                    error: None,
+                };
+            }
+        };
+
+        permanent &= response.0 == 301 || response.0 == 308;
+        current_url = match current_url.join(&location) {
+            Ok(next_url) => next_url,
+            Err(e) => {
+                return failed_check(
+                    current_url.as_str(),
+                    LinkState::Broken,
+                    format!("Invalid redirect '{}': {}", location, e),
+                )
+            }
+        };
+    }
+
+    failed_check(
+        current_url.as_str(),
+        LinkState::Broken,
+        String::from("Too many redirects."),
+    )
+}
+
+/// Link check of a link without a response.
+fn failed_check(url: &str, state: LinkState, error: String) -> LinkCheck {
+    LinkCheck {
+        state,
+        status: None,
+        final_url: String::from(url),
+        error: Some(error),
+    }
+}
+
+/// Status and redirect location of a url, using `GET` when the server doesn't allow `HEAD`.
+fn request_status(
+    agent: &ureq::Agent,
+    url: &url::Url,
+) -> Result<(u16, Option<String>), Box<ureq::Error>> {
+    let response = match agent.request_url("HEAD", url).call() {
+        Err(ureq::Error::Status(400 | 403 | 405 | 501, _)) => agent.request_url("GET", url).call(),
+        response => response,
+    };
+
+    match response {
+        Ok(response) => Ok((
+            response.status(),
+            response.header("location").map(String::from),
+        )),
+        Err(ureq::Error::Status(status, _)) => Ok((status, None)),
+        Err(e) => Err(Box::new(e)),
+    }
+}
+
+/// Whether a request failed because the server didn't answer in time.
+fn is_timeout(error: &ureq::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+    while let Some(e) = source {
+        if let Some(e) = e.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+            );
+        }
+        source = e.source();
+    }
+
+    false
+}
+
+/// Spaces out requests to the same host.
+struct RateLimiter {
+    delay: Duration,
+    /// When the next request to a host may start.
+    next_requests: Mutex<BTreeMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    fn new(delay: Duration) -> RateLimiter {
+        RateLimiter {
+            delay,
+            next_requests: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Waits until a request to the host may start.
+    fn wait(&self, host: &str) {
+        let start = {
+            let mut next_requests = self.next_requests.lock().unwrap();
+            let now = Instant::now();
+            let start = next_requests
+                .get(host)
+                .map(|next_request| (*next_request).max(now))
+                .unwrap_or(now);
+            next_requests.insert(String::from(host), start + self.delay);
+            start
+        };
+
+        let now = Instant::now();
+        if start > now {
+            std::thread::sleep(start - now);
+        }
+    }
+}
@@ -1,16 +1,45 @@
 extern crate anyhow;
-extern crate fsutils;
+extern crate base64;
+extern crate chrono;
+extern crate crossterm;
+extern crate fuzzy_matcher;
+extern crate image;
 extern crate log;
-extern crate regex;
+extern crate ratatui;
 
 use anyhow::{bail, Context, Result};
+use chrono::SubsecRound;
 use log::{LevelFilter, Record};
-use regex::Regex;
 use std::env;
 use std::io::Write;
 use std::str::FromStr;
 use std::{fs::File, path::PathBuf};
 
+mod archive;
+mod avatar;
+mod buku;
+mod chromium;
+mod content;
+mod dedupe;
+mod export;
+mod firefox;
+mod http;
+mod icons;
+mod import;
+mod index;
+mod launcher;
+mod links;
+mod markup;
+mod menu;
+mod metadata;
+mod netscape;
+mod prompt;
+mod rofi;
+mod startpage;
+mod tabular;
+mod tui;
+mod urls;
+
 type LogFormatter = Box<
     dyn Fn(&mut env_logger::fmt::Formatter, &Record) -> Result<(), std::io::Error> + Send + Sync,
 >;
@@ -20,10 +49,62 @@ struct Config {
     bookmarks: std::collections::BTreeMap<String, ConfigBookmark>,
 }
 
-#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 struct ConfigBookmark {
     url: String,
     tags: Vec<String>,
+    /// Folder path of the bookmark, like `Development/Rust`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    folder: Option<String>,
+    /// What the bookmarked page is about.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    /// Icon path used instead of the icon of the hostname.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    icon: Option<String>,
+    /// When the bookmark was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created: Option<chrono::DateTime<chrono::Utc>>,
+    /// Result of the last link check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    check: Option<ConfigLinkCheck>,
+    /// Offline snapshot of the page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    archive: Option<ConfigArchive>,
+    /// Previous urls of the bookmark, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    history: Vec<ConfigUrlChange>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct ConfigArchive {
+    /// Path of the snapshot.
+    path: String,
+    /// When the snapshot was saved.
+    archived: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct ConfigUrlChange {
+    /// Url before the change.
+    url: String,
+    /// When the url was changed.
+    changed: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct ConfigLinkCheck {
+    /// When the link was checked.
+    checked: chrono::DateTime<chrono::Utc>,
+    /// Status of the last response, none when there was no response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<u16>,
+    /// Url the bookmark redirects to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    /// Why the link couldn't be checked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
 type ExitCode = i32;
@@ -104,6 +185,15 @@ fn create_application() -> clap::App<'static, 'static> {
                 .default_value("~/.bookit")
                 .help("configuration file to use"),
         )
+        .arg(
+            clap::Arg::with_name("icons")
+                .global(true)
+                .long("icons")
+                .required(false)
+                .env("BOOKIT_ICONS_PATH")
+                .default_value("~/.icons/bookit")
+                .help("icon theme directory to use"),
+        )
         .subcommand(
             clap::SubCommand::with_name("completions")
                 .about("completions")
@@ -126,13 +216,310 @@ fn create_application() -> clap::App<'static, 'static> {
                         .required(false)
                         .takes_value(false)
                         .help("include icon for bookmarks"),
+                )
+                .arg(
+                    clap::Arg::with_name("launcher")
+                        .long("launcher")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(launcher::LAUNCHERS)
+                        .default_value("rofi")
+                        .help("output format of the launcher program"),
                 ),
         )
-        // TODO: Support for 'list-tags'.
-        //.subcommand(clap::SubCommand::with_name("list-tags").about("lists all tags recorded"))
         .subcommand(
-            clap::SubCommand::with_name("add")
-                .about("add a new bookmark")
+            clap::SubCommand::with_name("search")
+                .about("search bookmarks by name, url and tags")
+                .arg(
+                    clap::Arg::with_name("query")
+                        .required(true)
+                        .multiple(true)
+                        .takes_value(true)
+                        .help("terms that have to prefix a word of the bookmark"),
+                )
+                .arg(
+                    clap::Arg::with_name("content")
+                        .long("content")
+                        .required(false)
+                        .takes_value(false)
+                        .conflicts_with_all(&["launcher", "exclude-icon"])
+                        .help("search the text of bookmarked pages, ranked with snippets"),
+                )
+                .arg(
+                    clap::Arg::with_name("offline")
+                        .long("offline")
+                        .required(false)
+                        .takes_value(false)
+                        .requires("content")
+                        .help("search the stored text without downloading stale pages"),
+                )
+                .arg(
+                    clap::Arg::with_name("ttl")
+                        .long("ttl")
+                        .required(false)
+                        .takes_value(true)
+                        .requires("content")
+                        .help("days after which pages are downloaded again [default: 30]"),
+                )
+                .arg(
+                    clap::Arg::with_name("timeout")
+                        .long("timeout")
+                        .required(false)
+                        .takes_value(true)
+                        .requires("content")
+                        .help("seconds to wait for every request [default: 10]"),
+                )
+                .arg(
+                    clap::Arg::with_name("exclude-icon")
+                        .long("exclude-icon")
+                        .required(false)
+                        .takes_value(false)
+                        .help("include icon for bookmarks"),
+                )
+                .arg(
+                    clap::Arg::with_name("launcher")
+                        .long("launcher")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(launcher::LAUNCHERS)
+                        .default_value("rofi")
+                        .help("output format of the launcher program"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("rofi")
+                .about("rofi script mode, e.g. 'rofi -modi bookit:\"bookit rofi\" -show bookit'")
+                .arg(
+                    clap::Arg::with_name("selection")
+                        .required(false)
+                        .takes_value(true)
+                        .help("entry selected in rofi"),
+                )
+                .arg(
+                    clap::Arg::with_name("exclude-icon")
+                        .long("exclude-icon")
+                        .required(false)
+                        .takes_value(false)
+                        .help("include icon for bookmarks"),
+                )
+                .arg(
+                    clap::Arg::with_name("key-add")
+                        .long("key-add")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value("Ctrl+n")
+                        .help("key bound to '-kb-custom-1' shown in the message"),
+                )
+                .arg(
+                    clap::Arg::with_name("key-edit")
+                        .long("key-edit")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value("Ctrl+e")
+                        .help("key bound to '-kb-custom-2' shown in the message"),
+                )
+                .arg(
+                    clap::Arg::with_name("key-delete")
+                        .long("key-delete")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value("Ctrl+d")
+                        .help("key bound to '-kb-custom-3' shown in the message"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("menu")
+                .about("bookmark menu driving a dmenu compatible launcher")
+                .arg(
+                    clap::Arg::with_name("launcher")
+                        .long("launcher")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(menu::MENU_LAUNCHERS)
+                        .env("BOOKIT_MENU_LAUNCHER")
+                        .default_value("dmenu")
+                        .help("dmenu compatible launcher program"),
+                )
+                .arg(
+                    clap::Arg::with_name("command")
+                        .long("command")
+                        .required(false)
+                        .takes_value(true)
+                        .env("BOOKIT_MENU_COMMAND")
+                        .help("process command overriding the launcher program command"),
+                )
+                .arg(
+                    clap::Arg::with_name("exclude-icon")
+                        .long("exclude-icon")
+                        .required(false)
+                        .takes_value(false)
+                        .help("include icon for bookmarks"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("tui").about("browse and manage bookmarks in the terminal"),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("icons")
+                .about("bookmark icons")
+                .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    clap::SubCommand::with_name("sync")
+                        .about("downloads favicons of bookmarks into the icon theme")
+                        .arg(
+                            clap::Arg::with_name("ttl")
+                                .long("ttl")
+                                .required(false)
+                                .takes_value(true)
+                                .default_value("7")
+                                .help("days after which icons are downloaded again"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("force")
+                                .long("force")
+                                .required(false)
+                                .takes_value(false)
+                                .help("download icons regardless of their age"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("timeout")
+                                .long("timeout")
+                                .required(false)
+                                .takes_value(true)
+                                .default_value("10")
+                                .help("seconds to wait for every request"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("prune")
+                        .about("removes icons of hostnames no bookmark references")
+                        .arg(
+                            clap::Arg::with_name("dry-run")
+                                .long("dry-run")
+                                .required(false)
+                                .takes_value(false)
+                                .help("only list the icons to remove"),
+                        ),
+                )
+                .subcommand(
+                    clap::SubCommand::with_name("rebuild")
+                        .about("downloads the favicons of all bookmarks again")
+                        .arg(
+                            clap::Arg::with_name("dry-run")
+                                .long("dry-run")
+                                .required(false)
+                                .takes_value(false)
+                                .help("only list the icons to download"),
+                        )
+                        .arg(
+                            clap::Arg::with_name("timeout")
+                                .long("timeout")
+                                .required(false)
+                                .takes_value(true)
+                                .default_value("10")
+                                .help("seconds to wait for every request"),
+                        ),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("check-links")
+                .about("checks the urls of bookmarks for broken links and redirects")
+                .arg(
+                    clap::Arg::with_name("timeout")
+                        .long("timeout")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value("10")
+                        .help("seconds to wait for every request"),
+                )
+                .arg(
+                    clap::Arg::with_name("jobs")
+                        .long("jobs")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value("8")
+                        .help("number of links to check at the same time"),
+                )
+                .arg(
+                    clap::Arg::with_name("delay")
+                        .long("delay")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("milliseconds between two requests to the same host"),
+                )
+                .arg(
+                    clap::Arg::with_name("tag")
+                        .long("tag")
+                        .required(false)
+                        .takes_value(true)
+                        .help("tag added to broken bookmarks and removed from working ones"),
+                )
+                .arg(
+                    clap::Arg::with_name("fix-redirects")
+                        .long("fix-redirects")
+                        .required(false)
+                        .takes_value(false)
+                        .help("updates the urls of permanently redirected bookmarks"),
+                )
+                .arg(
+                    clap::Arg::with_name("yes")
+                        .long("yes")
+                        .required(false)
+                        .takes_value(false)
+                        .requires("fix-redirects")
+                        .help("updates urls without asking"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("undo-url")
+                .about("restores the previous url of a bookmark")
+                .arg(
+                    clap::Arg::with_name("name")
+                        .long("name")
+                        .required(true)
+                        .takes_value(true)
+                        .help("name of the bookmark"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("archive")
+                .about("saves offline snapshots of bookmarked pages")
+                .arg(
+                    clap::Arg::with_name("name")
+                        .short("n")
+                        .long("name")
+                        .required_unless("all")
+                        .conflicts_with("all")
+                        .takes_value(true)
+                        .help("name of the bookmark"),
+                )
+                .arg(
+                    clap::Arg::with_name("all")
+                        .long("all")
+                        .required(false)
+                        .takes_value(false)
+                        .help("archive all bookmarks without a snapshot"),
+                )
+                .arg(
+                    clap::Arg::with_name("force")
+                        .long("force")
+                        .required(false)
+                        .takes_value(false)
+                        .help("archive bookmarks again that already have a snapshot"),
+                )
+                .arg(
+                    clap::Arg::with_name("timeout")
+                        .long("timeout")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value("30")
+                        .help("seconds to wait for every request"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("open")
+                .about("opens a bookmark")
                 .arg(
                     clap::Arg::with_name("name")
                         .short("n")
@@ -141,6 +528,157 @@ fn create_application() -> clap::App<'static, 'static> {
                         .takes_value(true)
                         .help("name of the bookmark"),
                 )
+                .arg(
+                    clap::Arg::with_name("archived")
+                        .long("archived")
+                        .required(false)
+                        .takes_value(false)
+                        .help("opens the offline snapshot instead of the url"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("import")
+                .about("imports bookmarks from other programs")
+                .arg(
+                    clap::Arg::with_name("format")
+                        .long("format")
+                        .required(true)
+                        .takes_value(true)
+                        .possible_values(import::FORMATS)
+                        .help("format of the bookmarks"),
+                )
+                .arg(
+                    clap::Arg::with_name("file")
+                        .required(true)
+                        .takes_value(true)
+                        .help("file with the bookmarks, or the profile directory for firefox and chromium"),
+                )
+                .arg(
+                    clap::Arg::with_name("folders")
+                        .long("folders")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(import::FOLDER_MODES)
+                        .default_value("tags")
+                        .help("turns folders into tags or keeps their path as the bookmark's folder"),
+                )
+                .arg(
+                    clap::Arg::with_name("conflict")
+                        .long("conflict")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(import::CONFLICT_POLICIES)
+                        .default_value("skip")
+                        .help("what happens to bookmarks whose name is taken"),
+                )
+                .arg(
+                    clap::Arg::with_name("keywords")
+                        .long("keywords")
+                        .required(false)
+                        .takes_value(false)
+                        .help("adds the keywords of firefox bookmarks as tags"),
+                )
+                .arg(
+                    clap::Arg::with_name("map")
+                        .long("map")
+                        .required(false)
+                        .takes_value(true)
+                        .help("columns of csv and json bookmarks, like 'name=title,url=href,tags=tags'"),
+                )
+                .arg(
+                    clap::Arg::with_name("tag-separator")
+                        .long("tag-separator")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value(",")
+                        .help("separator of the tags of csv and json bookmarks"),
+                )
+                .arg(
+                    clap::Arg::with_name("tag")
+                        .long("tag")
+                        .required(false)
+                        .takes_value(true)
+                        .help("tag of links found in markdown, org and text files, the file name by default"),
+                )
+                .arg(
+                    clap::Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .required(false)
+                        .takes_value(false)
+                        .help("only list the bookmarks to import"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("export")
+                .about("exports bookmarks for other programs")
+                .arg(
+                    clap::Arg::with_name("format")
+                        .long("format")
+                        .required(true)
+                        .takes_value(true)
+                        .possible_values(export::FORMATS)
+                        .help("format of the bookmarks"),
+                )
+                .arg(
+                    clap::Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .required(false)
+                        .takes_value(true)
+                        .help("file to write the bookmarks to instead of STDOUT"),
+                )
+                .arg(
+                    clap::Arg::with_name("folders")
+                        .long("folders")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(import::FOLDER_MODES)
+                        .default_value("tags")
+                        .help("puts bookmarks in the folder of their first tag or of their folder path"),
+                ),
+        )
+        .subcommand(clap::SubCommand::with_name("list-tags").about("lists all tags recorded"))
+        .subcommand(
+            clap::SubCommand::with_name("dedupe")
+                .about("merges bookmarks with the same url")
+                .arg(
+                    clap::Arg::with_name("policy")
+                        .long("policy")
+                        .required(false)
+                        .takes_value(true)
+                        .possible_values(dedupe::POLICIES)
+                        .help("merges duplicates without asking, keeping the oldest bookmark"),
+                )
+                .arg(
+                    clap::Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .required(false)
+                        .takes_value(false)
+                        .help("only list the duplicates"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("normalize")
+                .about("normalizes the urls of all bookmarks")
+                .arg(
+                    clap::Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .required(false)
+                        .takes_value(false)
+                        .help("only list the urls to normalize"),
+                ),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("add")
+                .about("add a new bookmark")
+                .arg(
+                    clap::Arg::with_name("name")
+                        .short("n")
+                        .long("name")
+                        .required(false)
+                        .takes_value(true)
+                        .help("name of the bookmark, the title of the page when omitted"),
+                )
                 .arg(
                     clap::Arg::with_name("url")
                         .short("u")
@@ -153,10 +691,32 @@ fn create_application() -> clap::App<'static, 'static> {
                     clap::Arg::with_name("tags")
                         .short("t")
                         .long("tags")
-                        .required(true)
+                        .required(false)
                         .multiple(true)
                         .takes_value(true)
-                        .help("tags of the bookmark"),
+                        .help("tags of the bookmark, the keywords of the page when the name is omitted"),
+                )
+                .arg(
+                    clap::Arg::with_name("description")
+                        .short("d")
+                        .long("description")
+                        .required(false)
+                        .takes_value(true)
+                        .help("description of the bookmark"),
+                )
+                .arg(
+                    clap::Arg::with_name("no-fetch")
+                        .long("no-fetch")
+                        .required(false)
+                        .takes_value(false)
+                        .help("don't fetch the page for the details of the bookmark"),
+                )
+                .arg(
+                    clap::Arg::with_name("icon")
+                        .long("icon")
+                        .required(false)
+                        .takes_value(true)
+                        .help("icon path used instead of the icon of the website"),
                 )
                 .arg(
                     clap::Arg::with_name("force")
@@ -236,8 +796,34 @@ fn run(args: &clap::ArgMatches) -> Result<ExitCode> {
         command_config_create(args)?;
     } else if args.subcommand_matches("view").is_some() {
         command_view(args)?;
+    } else if args.subcommand_matches("search").is_some() {
+        command_search(args)?;
+    } else if args.subcommand_matches("rofi").is_some() {
+        command_rofi(args)?;
+    } else if args.subcommand_matches("menu").is_some() {
+        command_menu(args)?;
+    } else if args.subcommand_matches("tui").is_some() {
+        command_tui(args)?;
+    } else if args.subcommand_matches("icons").is_some() {
+        command_icons(args)?;
+    } else if args.subcommand_matches("check-links").is_some() {
+        command_check_links(args)?;
+    } else if args.subcommand_matches("undo-url").is_some() {
+        command_undo_url(args)?;
+    } else if args.subcommand_matches("archive").is_some() {
+        command_archive(args)?;
+    } else if args.subcommand_matches("open").is_some() {
+        command_open(args)?;
+    } else if args.subcommand_matches("import").is_some() {
+        command_import(args)?;
+    } else if args.subcommand_matches("export").is_some() {
+        command_export(args)?;
     } else if args.subcommand_matches("list-tags").is_some() {
-        command_list_tags()?;
+        command_list_tags(args)?;
+    } else if args.subcommand_matches("dedupe").is_some() {
+        command_dedupe(args)?;
+    } else if args.subcommand_matches("normalize").is_some() {
+        command_normalize(args)?;
     } else if args.subcommand_matches("add").is_some() {
         command_add(args)?;
     } else if args.subcommand_matches("edit").is_some() {
@@ -246,190 +832,814 @@ fn run(args: &clap::ArgMatches) -> Result<ExitCode> {
         command_delete(args)?;
     }
 
-    Ok(0)
+    Ok(0)
+}
+
+/// Command to output completions of a specific type to STDOUT.
+fn command_completions(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_completions = args.subcommand_matches("completions").unwrap();
+    let completion_type = args_completions.value_of("type").unwrap();
+
+    // Generate completion.
+    if completion_type == "bash" {
+        create_application().gen_completions_to(
+            create_application().get_bin_name().unwrap(),
+            clap::Shell::Bash,
+            &mut std::io::stdout(),
+        );
+    } else if completion_type == "elvish" {
+        create_application().gen_completions_to(
+            create_application().get_bin_name().unwrap(),
+            clap::Shell::Elvish,
+            &mut std::io::stdout(),
+        );
+    } else if completion_type == "fish" {
+        create_application().gen_completions_to(
+            create_application().get_bin_name().unwrap(),
+            clap::Shell::Fish,
+            &mut std::io::stdout(),
+        );
+    } else if completion_type == "powershell" {
+        create_application().gen_completions_to(
+            create_application().get_bin_name().unwrap(),
+            clap::Shell::PowerShell,
+            &mut std::io::stdout(),
+        );
+    } else if completion_type == "zsh" {
+        create_application().gen_completions_to(
+            create_application().get_bin_name().unwrap(),
+            clap::Shell::Zsh,
+            &mut std::io::stdout(),
+        );
+    } else {
+        bail!("Completion type '{}' not supported.", completion_type);
+    }
+
+    Ok(())
+}
+
+// Command to create a configuration file.
+fn command_config_create(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.subcommand_matches("config").unwrap();
+    let args_config_create = args_config.subcommand_matches("create").unwrap();
+    let args_config_create_config = args_config_create.value_of("create_config").unwrap();
+
+    // Get config path.
+    let config_path =
+        &std::path::PathBuf::from(shellexpand::tilde(args_config_create_config).into_owned());
+
+    // Does the config already exist?
+    if config_path.exists() {
+        bail!(
+            "Configuration file already exists at '{}'.",
+            config_path.display()
+        );
+    }
+
+    // Create the configuration file.
+    println!("Creating configuration at '{}'.", &config_path.display());
+
+    // Create path.
+    let mut config_directory_path = PathBuf::from(config_path);
+    config_directory_path.pop();
+    std::fs::create_dir_all(&config_directory_path)?;
+
+    // Create file.
+    let mut config_file = File::options()
+        .create_new(true)
+        .read(true)
+        .write(true)
+        .open(config_path)?;
+    config_file.write_all(
+        String::from(
+            r#"---
+bookmarks: {}"#,
+        )
+        .as_bytes(),
+    )?;
+
+    println!("Created configuration.");
+
+    Ok(())
+}
+
+/// Command to view bookmarks.
+fn command_view(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_view = args.subcommand_matches("view").unwrap();
+    let args_view_exclude_icon = args_view.is_present("exclude-icon");
+    let args_view_launcher: launcher::Launcher = args_view.value_of("launcher").unwrap().parse()?;
+
+    // Load index.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    let index = index::load_index(config_path)?;
+
+    // Print out every bookmark with corresponding context.
+    let entries: Vec<&index::IndexEntry> = index.entries().iter().collect();
+    args_view_launcher.write_entries(
+        &mut std::io::stdout().lock(),
+        &entries,
+        &launcher_options(args, !args_view_exclude_icon),
+    )?;
+
+    Ok(())
+}
+
+/// Command to search bookmarks.
+fn command_search(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_search = args.subcommand_matches("search").unwrap();
+    let args_search_query = args_search
+        .values_of("query")
+        .unwrap()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let args_search_exclude_icon = args_search.is_present("exclude-icon");
+    let args_search_launcher: launcher::Launcher =
+        args_search.value_of("launcher").unwrap().parse()?;
+
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    if args_search.is_present("content") {
+        return search_content(config_path, args_search, &args_search_query);
+    }
+
+    // Load index.
+    let index = index::load_index(config_path)?;
+
+    // Print out every matching bookmark with corresponding context.
+    args_search_launcher.write_entries(
+        &mut std::io::stdout().lock(),
+        &index.search(&args_search_query),
+        &launcher_options(args, !args_search_exclude_icon),
+    )?;
+
+    Ok(())
+}
+
+/// Searches the text of bookmarked pages, downloading new and stale pages first.
+fn search_content(
+    config_path: &std::path::Path,
+    args_search: &clap::ArgMatches,
+    query: &str,
+) -> Result<()> {
+    // Parse arguments.
+    let args_search_ttl: u64 = args_search
+        .value_of("ttl")
+        .unwrap_or("30")
+        .parse()
+        .context("Invalid '--ttl' days.")?;
+    let args_search_timeout: u64 = args_search
+        .value_of("timeout")
+        .unwrap_or("10")
+        .parse()
+        .context("Invalid '--timeout' seconds.")?;
+    let options = content::ContentOptions {
+        ttl: std::time::Duration::from_secs(args_search_ttl * 60 * 60 * 24),
+        timeout: std::time::Duration::from_secs(args_search_timeout),
+        jobs: 8,
+    };
+
+    // Refresh the content index.
+    let mut content_index = content::load_content_index(config_path);
+    if !args_search.is_present("offline") {
+        let config = load_config(config_path)?;
+        let fetched = content_index.refresh(&config, &options);
+        log::debug!("Downloaded {} pages.", fetched);
+        if let Err(e) = content::save_content_index(config_path, &content_index) {
+            log::warn!("Unable to save content index: {}", e);
+        }
+    }
+
+    // Print out the matching bookmarks, best first.
+    for content_match in content_index.search(query) {
+        println!(
+            "{}\t{}\t{}",
+            content_match.name, content_match.url, content_match.snippet
+        );
+    }
+
+    Ok(())
+}
+
+/// Command to list out bookmark tags.
+fn command_list_tags(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+
+    // Load index.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    let index = index::load_index(config_path)?;
+
+    // Print out every tag with the number of bookmarks using it.
+    for (tag, count) in index.tags() {
+        println!("{}\t{}", tag, count);
+    }
+
+    Ok(())
+}
+
+/// Command to run a step of rofi's script mode.
+fn command_rofi(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_rofi = args.subcommand_matches("rofi").unwrap();
+
+    // Get config path.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+
+    // Run the script mode step.
+    rofi::run(
+        config_path,
+        args_rofi.value_of("selection"),
+        &rofi::RofiOptions {
+            include_icon: !args_rofi.is_present("exclude-icon"),
+            key_add: String::from(args_rofi.value_of("key-add").unwrap()),
+            key_edit: String::from(args_rofi.value_of("key-edit").unwrap()),
+            key_delete: String::from(args_rofi.value_of("key-delete").unwrap()),
+        },
+        &mut std::io::stdout().lock(),
+    )
+}
+
+/// Command to run the bookmark menu.
+fn command_menu(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_menu = args.subcommand_matches("menu").unwrap();
+
+    // Get config path.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+
+    // Run the menu.
+    menu::run(
+        config_path,
+        &menu::MenuOptions {
+            launcher: args_menu.value_of("launcher").unwrap().parse()?,
+            command: args_menu.value_of("command").map(String::from),
+            launcher_options: launcher_options(args, !args_menu.is_present("exclude-icon")),
+        },
+    )
+}
+
+/// Command to run the terminal user interface.
+fn command_tui(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+
+    // Get config path.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+
+    // Run the terminal user interface.
+    tui::run(config_path)
+}
+
+/// Command to manage bookmark icons.
+fn command_icons(args: &clap::ArgMatches) -> Result<()> {
+    let args_icons = args.subcommand_matches("icons").unwrap();
+
+    if args_icons.subcommand_matches("sync").is_some() {
+        command_icons_sync(args)?;
+    } else if args_icons.subcommand_matches("prune").is_some() {
+        command_icons_prune(args)?;
+    } else if args_icons.subcommand_matches("rebuild").is_some() {
+        command_icons_rebuild(args)?;
+    }
+
+    Ok(())
+}
+
+/// Command to download favicons of bookmarks.
+fn command_icons_sync(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_icons = args.subcommand_matches("icons").unwrap();
+    let args_icons_sync = args_icons.subcommand_matches("sync").unwrap();
+    let args_icons_sync_ttl: u64 = args_icons_sync
+        .value_of("ttl")
+        .unwrap()
+        .parse()
+        .context("Invalid '--ttl' days.")?;
+    let args_icons_sync_timeout: u64 = args_icons_sync
+        .value_of("timeout")
+        .unwrap()
+        .parse()
+        .context("Invalid '--timeout' seconds.")?;
+    let options = icons::IconsOptions {
+        icons_path: PathBuf::from(shellexpand::tilde(args.value_of("icons").unwrap()).into_owned()),
+        ttl: std::time::Duration::from_secs(args_icons_sync_ttl * 60 * 60 * 24),
+        force: args_icons_sync.is_present("force"),
+        timeout: std::time::Duration::from_secs(args_icons_sync_timeout),
+    };
+
+    // Download icons.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    sync_icons(config_path, &options)
+}
+
+/// Command to remove icons of hostnames no bookmark references.
+fn command_icons_prune(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_icons = args.subcommand_matches("icons").unwrap();
+    let args_icons_prune = args_icons.subcommand_matches("prune").unwrap();
+    let args_icons_prune_dry_run = args_icons_prune.is_present("dry-run");
+    let icons_path =
+        PathBuf::from(shellexpand::tilde(args.value_of("icons").unwrap()).into_owned());
+
+    // Load index.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    let index = index::load_index(config_path)?;
+
+    // Remove stale icons.
+    let stale_icons = icons::stale_icons(&icons_path, &index)?;
+    for (icon_name, icon_paths) in &stale_icons {
+        if args_icons_prune_dry_run {
+            println!("Would remove icon '{}'.", icon_name);
+        } else {
+            for icon_path in icon_paths {
+                std::fs::remove_file(icon_path)
+                    .with_context(|| format!("Unable to remove icon '{}'.", icon_path.display()))?;
+            }
+            println!("Removed icon '{}'.", icon_name);
+        }
+    }
+    if args_icons_prune_dry_run {
+        println!("Would remove {} icons.", stale_icons.len());
+    } else {
+        println!("Removed {} icons.", stale_icons.len());
+    }
+
+    Ok(())
+}
+
+/// Command to download the favicons of all bookmarks again.
+fn command_icons_rebuild(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_icons = args.subcommand_matches("icons").unwrap();
+    let args_icons_rebuild = args_icons.subcommand_matches("rebuild").unwrap();
+    let args_icons_rebuild_dry_run = args_icons_rebuild.is_present("dry-run");
+    let args_icons_rebuild_timeout: u64 = args_icons_rebuild
+        .value_of("timeout")
+        .unwrap()
+        .parse()
+        .context("Invalid '--timeout' seconds.")?;
+    let options = icons::IconsOptions {
+        icons_path: PathBuf::from(shellexpand::tilde(args.value_of("icons").unwrap()).into_owned()),
+        ttl: std::time::Duration::from_secs(0),
+        force: true,
+        timeout: std::time::Duration::from_secs(args_icons_rebuild_timeout),
+    };
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+
+    // List icons.
+    if args_icons_rebuild_dry_run {
+        let index = index::load_index(config_path)?;
+        let sources = icons::icon_sources(&index);
+        let fallback_sources = icons::fallback_sources(&index);
+        for (hostname, page_url) in &sources {
+            println!("Would sync icon '{}' from '{}'.", hostname, page_url);
+        }
+        for icon_name in fallback_sources.keys() {
+            println!("Would generate icon '{}'.", icon_name);
+        }
+        println!(
+            "Would sync {} icons, generate {} icons.",
+            sources.len(),
+            fallback_sources.len()
+        );
+
+        return Ok(());
+    }
+
+    // Download every icon.
+    sync_icons(config_path, &options)
+}
+
+/// Downloads the favicons of bookmarks into the icon theme and prints a summary.
+///
+/// Bookmarks without a website, or whose website has no favicon, get a generated icon instead.
+fn sync_icons(config_path: &std::path::Path, options: &icons::IconsOptions) -> Result<()> {
+    let index = index::load_index(config_path)?;
+    icons::write_theme(&options.icons_path)?;
+
+    let agent = http::agent(options.timeout);
+    let (mut synced, mut generated, mut fresh, mut failed) = (0, 0, 0, 0);
+    let mut count = |icon_name: &str, icon_sync: Result<icons::IconSync>| match icon_sync {
+        Ok(icons::IconSync::Synced) => {
+            println!("Synced icon '{}'.", icon_name);
+            synced += 1;
+        }
+        Ok(icons::IconSync::Generated) => {
+            println!("Generated icon '{}'.", icon_name);
+            generated += 1;
+        }
+        Ok(icons::IconSync::Fresh) => fresh += 1,
+        Err(e) => {
+            log::warn!("Unable to sync icon '{}': {}", icon_name, e);
+            failed += 1;
+        }
+    };
+
+    // Favicons, keeping icons that were downloaded before.
+    for (hostname, page_url) in icons::icon_sources(&index) {
+        let icon_sync = match icons::sync_icon(&agent, &hostname, &page_url, options) {
+            Err(e) if !icons::icon_path(&options.icons_path, &hostname).exists() => {
+                log::debug!("No favicon for '{}': {}", hostname, e);
+                icons::generate_icon(&hostname, &avatar::initials(&hostname, true), options)
+            }
+            icon_sync => icon_sync,
+        };
+        count(&hostname, icon_sync);
+    }
+
+    // Generated icons.
+    for (icon_name, initials) in icons::fallback_sources(&index) {
+        count(
+            &icon_name,
+            icons::generate_icon(&icon_name, &initials, options),
+        );
+    }
+
+    println!(
+        "Synced {} icons, {} generated, {} up to date, {} failed.",
+        synced, generated, fresh, failed
+    );
+
+    Ok(())
+}
+
+/// Command to check the urls of bookmarks.
+fn command_check_links(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_check_links = args.subcommand_matches("check-links").unwrap();
+    let args_check_links_timeout: u64 = args_check_links
+        .value_of("timeout")
+        .unwrap()
+        .parse()
+        .context("Invalid '--timeout' seconds.")?;
+    let args_check_links_jobs: usize = args_check_links
+        .value_of("jobs")
+        .unwrap()
+        .parse()
+        .context("Invalid '--jobs' count.")?;
+    let args_check_links_delay: u64 = args_check_links
+        .value_of("delay")
+        .unwrap()
+        .parse()
+        .context("Invalid '--delay' milliseconds.")?;
+    let options = links::LinksOptions {
+        timeout: std::time::Duration::from_secs(args_check_links_timeout),
+        jobs: args_check_links_jobs,
+        delay: std::time::Duration::from_millis(args_check_links_delay),
+        tag: args_check_links.value_of("tag").map(String::from),
+        fix_redirects: args_check_links.is_present("fix-redirects"),
+        yes: args_check_links.is_present("yes"),
+    };
+
+    // Check links.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    links::run(
+        config_path,
+        &options,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stdout(),
+    )
+}
+
+/// Command to restore the previous url of a bookmark.
+fn command_undo_url(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_undo_url = args.subcommand_matches("undo-url").unwrap();
+    let args_undo_url_name = args_undo_url.value_of("name").unwrap();
+
+    // Restore url.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    let mut config = load_config(config_path)?;
+    let bookmark = match config.bookmarks.get_mut(args_undo_url_name) {
+        Some(bookmark) => bookmark,
+        None => bail!("Bookmark '{}' not found.", args_undo_url_name),
+    };
+    let change = match bookmark.history.pop() {
+        Some(change) => change,
+        None => bail!("Bookmark '{}' has no previous url.", args_undo_url_name),
+    };
+    bookmark.url = change.url;
+    bookmark.check = None;
+    println!(
+        "Restored bookmark '{}' to '{}'.",
+        args_undo_url_name, bookmark.url
+    );
+
+    // Save.
+    save_config(config_path, &config)
 }
 
-const REGEX_HOSTNAME: &str = r"^([^:]*://)([^/]*)/?.*?$";
-
-/// Command to output completions of a specific type to STDOUT.
-fn command_completions(args: &clap::ArgMatches) -> Result<()> {
+/// Command to save offline snapshots of bookmarked pages.
+fn command_archive(args: &clap::ArgMatches) -> Result<()> {
     // Parse arguments.
-    let args_completions = args.subcommand_matches("completions").unwrap();
-    let completion_type = args_completions.value_of("type").unwrap();
+    let args_config = args.value_of("config").unwrap();
+    let args_archive = args.subcommand_matches("archive").unwrap();
+    let args_archive_name = args_archive.value_of("name");
+    let args_archive_force = args_archive.is_present("force");
+    let args_archive_timeout: u64 = args_archive
+        .value_of("timeout")
+        .unwrap()
+        .parse()
+        .context("Invalid '--timeout' seconds.")?;
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    let options = archive::ArchiveOptions {
+        archive_path: archive::archive_path(config_path),
+        timeout: std::time::Duration::from_secs(args_archive_timeout),
+    };
 
-    // Generate completion.
-    if completion_type == "bash" {
-        create_application().gen_completions_to(
-            create_application().get_bin_name().unwrap(),
-            clap::Shell::Bash,
-            &mut std::io::stdout(),
-        );
-    } else if completion_type == "elvish" {
-        create_application().gen_completions_to(
-            create_application().get_bin_name().unwrap(),
-            clap::Shell::Elvish,
-            &mut std::io::stdout(),
-        );
-    } else if completion_type == "fish" {
-        create_application().gen_completions_to(
-            create_application().get_bin_name().unwrap(),
-            clap::Shell::Fish,
-            &mut std::io::stdout(),
-        );
-    } else if completion_type == "powershell" {
-        create_application().gen_completions_to(
-            create_application().get_bin_name().unwrap(),
-            clap::Shell::PowerShell,
-            &mut std::io::stdout(),
-        );
-    } else if completion_type == "zsh" {
-        create_application().gen_completions_to(
-            create_application().get_bin_name().unwrap(),
-            clap::Shell::Zsh,
-            &mut std::io::stdout(),
+    // Select bookmarks.
+    let mut config = load_config(config_path)?;
+    let names: Vec<String> = match args_archive_name {
+        Some(name) if config.bookmarks.contains_key(name) => vec![String::from(name)],
+        Some(name) => bail!("Bookmark '{}' not found.", name),
+        None => config
+            .bookmarks
+            .iter()
+            .filter(|(_, bookmark)| {
+                urls::parse(&bookmark.url)
+                    .map(|url| url.scheme() == "http" || url.scheme() == "https")
+                    .unwrap_or(false)
+            })
+            .map(|(name, _)| name.clone())
+            .collect(),
+    };
+
+    // Archive.
+    let (mut archived, mut fresh, mut failed) = (0, 0, 0);
+    for name in names {
+        let bookmark = config.bookmarks.get_mut(&name).unwrap();
+        if !args_archive_force && bookmark.archive.is_some() && args_archive_name.is_none() {
+            fresh += 1;
+            continue;
+        }
+
+        match archive::archive_page(&name, &bookmark.url, &options) {
+            Ok(snapshot_path) => {
+                println!(
+                    "Archived bookmark '{}' to '{}'.",
+                    name,
+                    snapshot_path.display()
+                );
+                bookmark.archive = Some(ConfigArchive {
+                    path: snapshot_path.display().to_string(),
+                    archived: chrono::Utc::now().trunc_subsecs(0),
+                });
+                archived += 1;
+            }
+            Err(e) if args_archive_name.is_some() => {
+                bail!("Unable to archive bookmark '{}': {}", name, e)
+            }
+            Err(e) => {
+                log::warn!("Unable to archive bookmark '{}': {}", name, e);
+                failed += 1;
+            }
+        }
+    }
+
+    // Save.
+    if archived > 0 {
+        save_config(config_path, &config)?;
+    }
+    if args_archive_name.is_none() {
+        println!(
+            "Archived {} bookmarks, {} already archived, {} failed.",
+            archived, fresh, failed
         );
-    } else {
-        bail!("Completion type '{}' not supported.", completion_type);
     }
 
     Ok(())
 }
 
-// Command to create a configuration file.
-fn command_config_create(args: &clap::ArgMatches) -> Result<()> {
+/// Command to open a bookmark or its offline snapshot.
+fn command_open(args: &clap::ArgMatches) -> Result<()> {
     // Parse arguments.
-    let args_config = args.subcommand_matches("config").unwrap();
-    let args_config_create = args_config.subcommand_matches("create").unwrap();
-    let args_config_create_config = args_config_create.value_of("create_config").unwrap();
+    let args_config = args.value_of("config").unwrap();
+    let args_open = args.subcommand_matches("open").unwrap();
+    let args_open_name = args_open.value_of("name").unwrap();
 
-    // Get config path.
-    let config_path =
-        &std::path::PathBuf::from(shellexpand::tilde(args_config_create_config).into_owned());
+    // Find the bookmark.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    let config = load_config(config_path)?;
+    let bookmark = match config.bookmarks.get(args_open_name) {
+        Some(bookmark) => bookmark,
+        None => bail!("Bookmark '{}' not found.", args_open_name),
+    };
 
-    // Does the config already exist?
-    if config_path.exists() {
-        bail!(
-            "Configuration file already exists at '{}'.",
-            config_path.display()
-        );
+    // Open it.
+    if !args_open.is_present("archived") {
+        return open_url(&bookmark.url);
     }
+    let snapshot_path = match &bookmark.archive {
+        Some(archive) => shellexpand::tilde(&archive.path).into_owned(),
+        None => bail!("Bookmark '{}' has no snapshot.", args_open_name),
+    };
+    if !std::path::Path::new(&snapshot_path).exists() {
+        bail!("Snapshot '{}' does not exist.", snapshot_path);
+    }
+    open_url(&snapshot_path)
+}
 
-    // Create the configuration file.
-    println!("Creating configuration at '{}'.", &config_path.display());
-
-    // Create path.
-    let mut config_directory_path = PathBuf::from(config_path);
-    config_directory_path.pop();
-    fsutils::mkdir(config_directory_path.as_os_str().to_str().unwrap());
+/// Command to import bookmarks from other programs.
+fn command_import(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_config = args.value_of("config").unwrap();
+    let args_import = args.subcommand_matches("import").unwrap();
+    let args_import_format = args_import.value_of("format").unwrap();
+    let args_import_file = std::path::PathBuf::from(
+        shellexpand::tilde(args_import.value_of("file").unwrap()).into_owned(),
+    );
+    let options = import::ImportOptions {
+        folders: args_import.value_of("folders").unwrap().parse()?,
+        conflict: args_import.value_of("conflict").unwrap().parse()?,
+        dry_run: args_import.is_present("dry-run"),
+    };
+    for (option, formats) in [
+        ("map", &["csv", "json"][..]),
+        ("tag-separator", &["csv", "json"]),
+        ("tag", &["markdown", "org", "text"]),
+        ("keywords", &["firefox"]),
+    ] {
+        if args_import.occurrences_of(option) > 0 && !formats.contains(&args_import_format) {
+            bail!(
+                "Option '--{}' doesn't apply to import format '{}'.",
+                option,
+                args_import_format
+            );
+        }
+    }
+    let tabular_options = tabular::TabularOptions {
+        map: args_import.value_of("map").unwrap_or("").parse()?,
+        tag_separator: String::from(args_import.value_of("tag-separator").unwrap()),
+    };
 
-    // Create file.
-    let mut config_file = File::options()
-        .create_new(true)
-        .read(true)
-        .write(true)
-        .open(config_path)?;
-    config_file.write_all(
-        String::from(
-            r#"---
-bookmarks: {}"#,
-        )
-        .as_bytes(),
-    )?;
+    // Read bookmarks.
+    let bookmarks = match args_import_format {
+        "netscape" => {
+            let html = std::fs::read_to_string(&args_import_file)
+                .with_context(|| format!("Unable to read '{}'.", args_import_file.display()))?;
+            netscape::parse(&html)
+        }
+        "chromium" => chromium::read(&args_import_file)?,
+        "buku" => buku::read(&args_import_file)?,
+        "csv" | "json" => {
+            let contents = std::fs::read_to_string(&args_import_file)
+                .with_context(|| format!("Unable to read '{}'.", args_import_file.display()))?;
+            if args_import_format == "csv" {
+                tabular::parse_csv(&contents, &tabular_options)?
+            } else {
+                tabular::parse_json(&contents, &tabular_options)?
+            }
+        }
+        "markdown" | "org" | "text" => {
+            let contents = std::fs::read_to_string(&args_import_file)
+                .with_context(|| format!("Unable to read '{}'.", args_import_file.display()))?;
+            let tag = match args_import.value_of("tag") {
+                Some(tag) => String::from(tag.trim()),
+                None => icons::slug(
+                    &args_import_file
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy(),
+                ),
+            };
 
-    println!("Created configuration.");
+            let mut bookmarks = markup::parse(&contents, args_import_format);
+            if !tag.is_empty() {
+                for bookmark in bookmarks.iter_mut() {
+                    bookmark.tags.push(tag.clone());
+                }
+            }
+            bookmarks
+        }
+        "firefox" => firefox::read(&args_import_file, args_import.is_present("keywords"))?,
+        _ => bail!("Import format '{}' not supported.", args_import_format),
+    };
 
-    Ok(())
+    // Import.
+    let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
+    import::run(config_path, bookmarks, &options, &mut std::io::stdout())
 }
 
-/// Command to view bookmarks.
-fn command_view(args: &clap::ArgMatches) -> Result<()> {
+/// Command to export bookmarks for other programs.
+fn command_export(args: &clap::ArgMatches) -> Result<()> {
     // Parse arguments.
     let args_config = args.value_of("config").unwrap();
-    let args_view = args.subcommand_matches("view").unwrap();
-    let args_view_exclude_icon = args_view.is_present("exclude-icon");
+    let args_export = args.subcommand_matches("export").unwrap();
+    let args_export_format = args_export.value_of("format").unwrap();
+    let args_export_folders: import::FolderMode =
+        args_export.value_of("folders").unwrap().parse()?;
 
-    // Load config.
+    // Write bookmarks.
     let config_path = &std::path::PathBuf::from(shellexpand::tilde(args_config).into_owned());
     let config = load_config(config_path)?;
+    if args_export_format == "buku" {
+        // Buku keeps bookmarks in a database instead of a file to print.
+        let output_path = match args_export.value_of("output") {
+            Some(output) => shellexpand::tilde(output).into_owned(),
+            None => bail!("Export format 'buku' requires '--output'."),
+        };
+        let exported = buku::write(std::path::Path::new(&output_path), &config)?;
+        println!("Exported {} bookmarks to '{}'.", exported, output_path);
+        return Ok(());
+    }
+    let root = export::folder_tree(&config, args_export_folders);
+    let contents = match args_export_format {
+        "netscape" => netscape::write(&root),
+        "markdown" => markup::write_markdown(&root),
+        "org" => markup::write_org(&root),
+        "html" => {
+            let icons_path =
+                PathBuf::from(shellexpand::tilde(args.value_of("icons").unwrap()).into_owned());
+            let index = index::load_index(config_path)?;
+            startpage::write(&root, &startpage::icon_data_urls(&index, &icons_path))
+        }
+        _ => bail!("Export format '{}' not supported.", args_export_format),
+    };
 
-    // Print out every bookmark with corresponding context.
-    for bookmark in config.bookmarks.iter() {
-        let re = Regex::new(REGEX_HOSTNAME).unwrap();
-        if let Some(captures) = re.captures(&bookmark.1.url) {
-            let hostname = captures.get(2).unwrap().as_str();
-
-            println!(
-                "{}\t{}\t{}{}",
-                bookmark.0,
-                bookmark.1.tags.join(","),
-                bookmark.1.url,
-                if !args_view_exclude_icon {
-                    format!("\t\0icon\x1f{}", hostname)
-                } else {
-                    String::from("")
-                },
-            );
-        } else {
-            bail!(
-                "Cannot parse bookmark entry '{}' not a valid entry.",
-                &bookmark.1.url
-            )
+    // Output.
+    match args_export.value_of("output") {
+        Some(output) => {
+            let output_path = shellexpand::tilde(output).into_owned();
+            std::fs::write(&output_path, contents)
+                .with_context(|| format!("Unable to write '{}'.", output_path))?;
+            println!("Exported {} bookmarks to '{}'.", root.len(), output_path);
         }
+        None => print!("{}", contents),
     }
 
     Ok(())
 }
 
-/// Command to list out bookmark tags.
-fn command_list_tags() -> Result<()> {
-    // TODO@nw:
-    Ok(())
-}
-
 /// Command to add a bookmark.
 fn command_add(args: &clap::ArgMatches) -> Result<()> {
     // Parse arguments.
     let args_add = args.subcommand_matches("add").unwrap();
 
-    // Load config.
+    // Get config path.
     let config_path = &std::path::PathBuf::from(
         shellexpand::tilde(args.value_of("config").unwrap()).into_owned(),
     );
-    let mut config = load_config(config_path)?;
 
     // Parse bookmark details.
-    let name = args_add.value_of("name").unwrap();
     let url = args_add.value_of("url").unwrap();
-    let tags = args_add.values_of("tags").unwrap();
+    let mut name = args_add.value_of("name").map(String::from);
+    let mut tags: Option<Vec<String>> = args_add
+        .values_of("tags")
+        .map(|tags| tags.map(String::from).collect());
+    let mut description = args_add.value_of("description").map(String::from);
 
-    // Check if it already exists.
-    if config.bookmarks.contains_key(name) {
-        if !args_add.is_present("force") {
-            anyhow::bail!(
-                "Bookmark already exists with name '{}'. Use '--force' to override.",
-                name
-            );
-        } else {
-            config.bookmarks.remove(name);
+    // Reject urls that can't be parsed before fetching them.
+    let (page_url, _) = urls::normalize(url, &urls::NormalizeOptions::from_env());
+    let page_scheme = String::from(urls::parse(&page_url)?.scheme());
+
+    // Fill in the details from web pages when the name is omitted.
+    if name.is_none()
+        && !args_add.is_present("no-fetch")
+        && (page_scheme == "http" || page_scheme == "https")
+    {
+        match metadata::fetch(&page_url) {
+            Ok(page) => {
+                name = page.title;
+                description = description.or(page.description);
+                tags = tags.or(Some(page.tags));
+            }
+            Err(e) => log::warn!("Unable to fetch '{}': {}", url, e),
         }
     }
+    let name = name.unwrap_or_else(|| String::from(url));
 
-    // Insert the new bookmark.
-    config.bookmarks.insert(
-        String::from(name),
+    // Normalize the url.
+    let (url, warnings) = normalize_bookmark_url(config_path, &name, url)?;
+    for warning in warnings {
+        log::warn!("{}", warning);
+    }
+
+    // Add the bookmark.
+    add_bookmark(
+        config_path,
+        &name,
         ConfigBookmark {
-            url: String::from(url),
-            tags: tags.map(String::from).collect(),
+            url: url.clone(),
+            tags: tags.unwrap_or_default(),
+            description,
+            icon: args_add.value_of("icon").map(String::from),
+            ..ConfigBookmark::default()
         },
-    );
-
-    // Save.
-    std::fs::write(config_path, serde_yaml::to_string(&config)?)?;
-    println!(
-        "Added bookmark '{}\t{}'.",
-        String::from(name),
-        String::from(url)
-    );
+        args_add.is_present("force"),
+    )?;
+    println!("Added bookmark '{}\t{}'.", name, urls::display(&url));
 
     Ok(())
 }
@@ -485,7 +1695,7 @@ fn command_edit(args: &clap::ArgMatches) -> Result<()> {
         .iter()
         .map(AsRef::as_ref)
         .collect();
-    let bookit_edit_command_quoted = shlex::join(refs_bookit_edit_command_quoted_split);
+    let bookit_edit_command_quoted = shlex::try_join(refs_bookit_edit_command_quoted_split)?;
     log::info!("Command Quoted: {}", bookit_edit_command_quoted);
 
     // Quote any arguments correctly.
@@ -504,35 +1714,275 @@ fn command_edit(args: &clap::ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// Command to merge bookmarks with the same url.
+fn command_dedupe(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_dedupe = args.subcommand_matches("dedupe").unwrap();
+    let options = dedupe::DedupeOptions {
+        policy: args_dedupe
+            .value_of("policy")
+            .map(dedupe::DedupePolicy::from_str)
+            .transpose()?,
+        dry_run: args_dedupe.is_present("dry-run"),
+    };
+
+    // Get config path.
+    let config_path = &std::path::PathBuf::from(
+        shellexpand::tilde(args.value_of("config").unwrap()).into_owned(),
+    );
+
+    // Merge duplicates.
+    dedupe::run(
+        config_path,
+        &options,
+        &mut std::io::stdin().lock(),
+        &mut std::io::stdout(),
+    )
+}
+
+/// Command to normalize the urls of all bookmarks.
+fn command_normalize(args: &clap::ArgMatches) -> Result<()> {
+    // Parse arguments.
+    let args_normalize = args.subcommand_matches("normalize").unwrap();
+    let args_normalize_dry_run = args_normalize.is_present("dry-run");
+
+    // Load config.
+    let config_path = &std::path::PathBuf::from(
+        shellexpand::tilde(args.value_of("config").unwrap()).into_owned(),
+    );
+    let mut config = load_config(config_path)?;
+
+    // Normalize every url.
+    let options = urls::NormalizeOptions::from_env();
+    let mut normalized = 0;
+    for (name, bookmark) in config.bookmarks.iter_mut() {
+        let (url, warnings) = urls::normalize(&bookmark.url, &options);
+        for warning in warnings {
+            log::warn!("Bookmark '{}': {}", name, warning);
+        }
+        if url == bookmark.url {
+            continue;
+        }
+
+        println!(
+            "{} bookmark '{}' from '{}' to '{}'.",
+            if args_normalize_dry_run {
+                "Would normalize"
+            } else {
+                "Normalized"
+            },
+            name,
+            bookmark.url,
+            url
+        );
+        bookmark.url = url;
+        normalized += 1;
+    }
+
+    // Save.
+    if args_normalize_dry_run {
+        println!("Would normalize {} bookmarks.", normalized);
+    } else {
+        if normalized > 0 {
+            save_config(config_path, &config)?;
+        }
+        println!("Normalized {} bookmarks.", normalized);
+    }
+
+    Ok(())
+}
+
 /// Command to delete a bookmark.
 fn command_delete(args: &clap::ArgMatches) -> Result<()> {
     // Parse arguments.
     let args_delete = args.subcommand_matches("delete").unwrap();
     let args_delete_name = args_delete.value_of("name").unwrap();
 
-    // Load config.
+    // Get config path.
     let config_path = &std::path::PathBuf::from(
         shellexpand::tilde(args.value_of("config").unwrap()).into_owned(),
     );
+
+    // Delete the bookmark.
+    delete_bookmark(config_path, args_delete_name)?;
+    println!("Deleted bookmark '{}'.", String::from(args_delete_name),);
+
+    Ok(())
+}
+
+/// Normalizes the url of a new bookmark, see `urls::normalize`.
+///
+/// Returns warnings about the url and about other bookmarks with the same url.
+fn normalize_bookmark_url(
+    config_path: &std::path::Path,
+    name: &str,
+    url: &str,
+) -> Result<(String, Vec<String>)> {
+    let options = urls::NormalizeOptions::from_env();
+    let (url, mut warnings) = urls::normalize(url, &options);
+
+    // Check for duplicates.
+    let config = load_config(config_path)?;
+    let key = urls::duplicate_key(&url, &options);
+    let duplicates: Vec<&str> = config
+        .bookmarks
+        .iter()
+        .filter(|(other_name, other)| {
+            other_name.as_str() != name && urls::duplicate_key(&other.url, &options) == key
+        })
+        .map(|(other_name, _)| other_name.as_str())
+        .collect();
+    if !duplicates.is_empty() {
+        warnings.push(format!(
+            "Url '{}' already exists as bookmark '{}'.",
+            url,
+            duplicates.join("', '")
+        ));
+    }
+
+    Ok((url, warnings))
+}
+
+/// Adds a bookmark to a configuration file.
+fn add_bookmark(
+    config_path: &std::path::Path,
+    name: &str,
+    mut bookmark: ConfigBookmark,
+    force: bool,
+) -> Result<()> {
+    // Load config.
+    let mut config = load_config(config_path)?;
+
+    // Check if it already exists.
+    if config.bookmarks.contains_key(name) {
+        if !force {
+            anyhow::bail!(
+                "Bookmark already exists with name '{}'. Use '--force' to override.",
+                name
+            );
+        } else {
+            config.bookmarks.remove(name);
+        }
+    }
+
+    // Insert the new bookmark.
+    if bookmark.created.is_none() {
+        bookmark.created = Some(chrono::Utc::now().trunc_subsecs(0));
+    }
+    config.bookmarks.insert(String::from(name), bookmark);
+
+    // Save.
+    save_config(config_path, &config)
+}
+
+/// Fills in the name of a new bookmark from its page, or falls back to the url, when no name was
+/// given.
+///
+/// Returns the name and the page details, which are empty when the page wasn't fetched.
+fn page_details(url: &str, name: &str) -> (String, metadata::PageMetadata) {
+    if !name.is_empty() {
+        return (String::from(name), metadata::PageMetadata::default());
+    }
+
+    // Only fetch web pages.
+    let (page_url, _) = urls::normalize(url, &urls::NormalizeOptions::from_env());
+    let is_web_page = urls::parse(&page_url)
+        .map(|parsed| parsed.scheme() == "http" || parsed.scheme() == "https")
+        .unwrap_or(false);
+    let mut page = if is_web_page {
+        metadata::fetch(&page_url).unwrap_or_else(|e| {
+            log::debug!("Unable to fetch '{}': {}", url, e);
+            metadata::PageMetadata::default()
+        })
+    } else {
+        metadata::PageMetadata::default()
+    };
+    let name = page.title.take().unwrap_or_else(|| String::from(url));
+
+    (name, page)
+}
+
+/// Deletes a bookmark from a configuration file.
+fn delete_bookmark(config_path: &std::path::Path, name: &str) -> Result<()> {
+    // Load config.
     let mut config = load_config(config_path)?;
 
     // Check if it already exists.
-    if !config.bookmarks.contains_key(args_delete_name) {
-        bail!("Bookmark doesn't exist with name '{}'.", args_delete_name);
+    if !config.bookmarks.contains_key(name) {
+        bail!("Bookmark doesn't exist with name '{}'.", name);
     }
 
     // Remove bookmark.
-    config.bookmarks.remove(args_delete_name);
+    config.bookmarks.remove(name);
 
     // Save.
-    std::fs::write(config_path, serde_yaml::to_string(&config)?)?;
-    println!("Deleted bookmark '{}'.", String::from(args_delete_name),);
+    save_config(config_path, &config)
+}
+
+/// Opens a url with `$BOOKIT_OPEN_COMMAND` without waiting for it.
+fn open_url(url: &str) -> Result<()> {
+    let bookit_open_command =
+        std::env::var("BOOKIT_OPEN_COMMAND").unwrap_or_else(|_| String::from("xdg-open"));
+    log::debug!("Open: {} {}", bookit_open_command, url);
+
+    let mut bookit_open_command_parts = shlex::split(&bookit_open_command)
+        .with_context(|| format!("Invalid open command '{}'.", bookit_open_command))?;
+    bookit_open_command_parts.push(String::from(url));
+
+    spawn_detached(&bookit_open_command_parts)
+}
+
+/// Opens a web search with `$BOOKIT_SEARCH_URL` for input that isn't a bookmark.
+fn open_search(query: &str) -> Result<()> {
+    let bookit_search_url = std::env::var("BOOKIT_SEARCH_URL")
+        .unwrap_or_else(|_| String::from("https://www.google.com/search?q=$BOOKIT_SEARCH_QUERY"));
+
+    open_url(&bookit_search_url.replace(
+        "$BOOKIT_SEARCH_QUERY",
+        &url::form_urlencoded::byte_serialize(query.as_bytes()).collect::<String>(),
+    ))
+}
+
+/// Runs a command inside `$BOOKIT_TERMINAL_COMMAND` without waiting for it.
+fn spawn_terminal(command: &[String]) -> Result<()> {
+    let bookit_terminal_command = std::env::var("BOOKIT_TERMINAL_COMMAND")
+        .unwrap_or_else(|_| String::from("x-terminal-emulator -e"));
+    log::debug!("Terminal: {} {:?}", bookit_terminal_command, command);
+
+    let mut bookit_terminal_command_parts = shlex::split(&bookit_terminal_command)
+        .with_context(|| format!("Invalid terminal command '{}'.", bookit_terminal_command))?;
+    bookit_terminal_command_parts.extend(command.iter().cloned());
+
+    spawn_detached(&bookit_terminal_command_parts)
+}
+
+/// Spawns a process detached from our standard streams so launchers don't wait on it.
+fn spawn_detached(command_parts: &[String]) -> Result<()> {
+    if command_parts.is_empty() {
+        bail!("Command is empty.");
+    }
+
+    std::process::Command::new(&command_parts[0])
+        .args(&command_parts[1..])
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .with_context(|| format!("Unable to run '{}'.", command_parts[0]))?;
 
     Ok(())
 }
 
+/// Launcher options from the global command line interface arguments.
+fn launcher_options(args: &clap::ArgMatches, include_icon: bool) -> launcher::LauncherOptions {
+    launcher::LauncherOptions {
+        include_icon,
+        icons_path: PathBuf::from(shellexpand::tilde(args.value_of("icons").unwrap()).into_owned()),
+    }
+}
+
 /// Loads a bookit configuration file.
-fn load_config(config_path: &std::path::PathBuf) -> Result<Config> {
+fn load_config(config_path: &std::path::Path) -> Result<Config> {
     // Load config file.
     let contents = std::fs::read_to_string(config_path)
         .with_context(|| format!("No config found at '{}'.", config_path.display()))?;
@@ -542,3 +1992,15 @@ fn load_config(config_path: &std::path::PathBuf) -> Result<Config> {
 
     Ok(config)
 }
+
+/// Saves a bookit configuration file.
+fn save_config(config_path: &std::path::Path, config: &Config) -> Result<()> {
+    std::fs::write(config_path, serde_yaml::to_string(config)?)?;
+
+    Ok(())
+}
+
+/// Text with whitespace collapsed.
+fn clean_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
@@ -0,0 +1,255 @@
+use super::clean_text;
+use super::export::Folder;
+use super::import::ImportedBookmark;
+use std::fmt::Write;
+
+/// Schemes of bare urls found in text.
+const BARE_URL_PREFIXES: &[&str] = &["https://", "http://"];
+
+/// Schemes of links that are bookmarked, links like org's `file:`, `id:` and `elisp:` or
+/// `javascript:` don't point to web pages.
+const LINK_SCHEMES: &[&str] = &["https", "http"];
+
+/// Characters ending a sentence rather than a bare url.
+const TRAILING_PUNCTUATION: &[char] =
+    &['.', ',', ';', ':', '!', '?', '\'', '"', ')', ']', '*', '_'];
+
+/// Reads the links of a document.
+///
+/// Markdown links look like `[title](url)` or `<url>`, org links like `[[url][title]]` or `[[url]]`
+/// are found in markdown and org documents and bare `http://` and `https://` urls in every format. The link text becomes the
+/// name of the bookmark, links whose target isn't an `http` or `https` url are skipped.
+pub fn parse(text: &str, format: &str) -> Vec<ImportedBookmark> {
+    let mut links: Vec<(String, String)> = Vec::new();
+    let mut position = 0;
+    while position < text.len() {
+        let rest = &text[position..];
+        let link = match format {
+            "markdown" => markdown_link(text, position).or_else(|| org_link(rest)),
+            "org" => org_link(rest),
+            _ => None,
+        }
+        .or_else(|| bare_url(text, position));
+
+        match link {
+            Some((length, name, url)) => {
+                links.push((name, url));
+                position += length;
+            }
+            None => position += rest.chars().next().map(char::len_utf8).unwrap_or(1),
+        }
+    }
+
+    // Keep every url once, preferring a link with a title over a bare url.
+    let mut bookmarks: Vec<ImportedBookmark> = Vec::new();
+    for (name, url) in links {
+        if url.is_empty() {
+            continue;
+        }
+        match url::Url::parse(&url) {
+            Ok(parsed) if LINK_SCHEMES.contains(&parsed.scheme()) => {}
+            _ => {
+                log::debug!("Skipped link '{}', it's not a web url.", url);
+                continue;
+            }
+        }
+        match bookmarks.iter_mut().find(|bookmark| bookmark.url == url) {
+            Some(bookmark) if bookmark.name.is_empty() => bookmark.name = name,
+            Some(_) => {}
+            None => bookmarks.push(ImportedBookmark {
+                name,
+                url,
+                ..ImportedBookmark::default()
+            }),
+        }
+    }
+
+    bookmarks
+}
+
+/// Length, text and url of a markdown link or autolink at the start of `text[position..]`.
+fn markdown_link(text: &str, position: usize) -> Option<(usize, String, String)> {
+    let rest = &text[position..];
+
+    // Autolinks like `<https://example.com>`.
+    if let Some(autolink) = rest.strip_prefix('<') {
+        let end = autolink.find('>')?;
+        let url = &autolink[..end];
+        if url.contains(char::is_whitespace) || !url.contains(':') {
+            return None;
+        }
+        return Some((end + 2, String::new(), String::from(url)));
+    }
+
+    // Images like `![alt](url)` are skipped as a whole, without a url.
+    if rest.starts_with("![") {
+        let (length, _, _) = markdown_link(text, position + 1)?;
+        return Some((length + 1, String::new(), String::new()));
+    }
+
+    // Links like `[title](url "tooltip")`.
+    if !rest.starts_with('[') {
+        return None;
+    }
+    let title_end = matching(rest, '[', ']')?;
+    let destination = rest[title_end + 1..].strip_prefix('(')?;
+    let (url, destination_end) = match destination.strip_prefix('<') {
+        // Destinations like `<https://example.com/a b>` can hold spaces and parentheses.
+        Some(bracketed) => {
+            let url_end = bracketed
+                .find(['>', '\n'])
+                .filter(|end| bracketed[*end..].starts_with('>'))?;
+            let tooltip = &bracketed[url_end + 1..];
+            let tooltip_end = tooltip
+                .find([')', '\n'])
+                .filter(|end| tooltip[*end..].starts_with(')'))?;
+            (&bracketed[..url_end], url_end + 2 + tooltip_end)
+        }
+        None => {
+            let destination_end = matching(&rest[title_end + 1..], '(', ')')? - 1;
+            (
+                destination[..destination_end].split_whitespace().next()?,
+                destination_end,
+            )
+        }
+    };
+
+    Some((
+        title_end + 1 + destination_end + 2,
+        clean_text(&rest[1..title_end])
+            .replace("\\[", "[")
+            .replace("\\]", "]"),
+        String::from(url),
+    ))
+}
+
+/// Length, text and url of an org link at the start of `text`.
+fn org_link(text: &str) -> Option<(usize, String, String)> {
+    let link = text.strip_prefix("[[")?;
+    let end = link.find("]]")?;
+    let (url, name) = match link[..end].split_once("][") {
+        Some((url, name)) => (url, clean_text(name)),
+        None => (&link[..end], String::new()),
+    };
+
+    Some((end + 4, name, String::from(url.trim())))
+}
+
+/// Length and url of a bare url at the start of `text[position..]`.
+fn bare_url(text: &str, position: usize) -> Option<(usize, String, String)> {
+    let rest = &text[position..];
+    let starts_word = !text[..position]
+        .chars()
+        .next_back()
+        .is_some_and(char::is_alphanumeric);
+    if !starts_word
+        || !BARE_URL_PREFIXES.iter().any(|prefix| {
+            rest.get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        })
+    {
+        return None;
+    }
+
+    let end = rest
+        .find(|c: char| c.is_whitespace() || c == '<' || c == '>' || c == '"' || c == '`')
+        .unwrap_or(rest.len());
+    let mut url = &rest[..end];
+
+    // Drop punctuation after the url, keeping closing parentheses that belong to it.
+    while let Some(last) = url.chars().next_back() {
+        let unbalanced = url.matches(')').count() > url.matches('(').count();
+        if !TRAILING_PUNCTUATION.contains(&last) || (last == ')' && !unbalanced) {
+            break;
+        }
+        url = &url[..url.len() - last.len_utf8()];
+    }
+    Some((url.len(), String::new(), String::from(url)))
+}
+
+/// Position of the bracket closing the one `text` starts with, on the same line.
+fn matching(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (position, c) in text.char_indices() {
+        if c == '\n' {
+            return None;
+        }
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(position);
+            }
+        }
+    }
+
+    None
+}
+
+/// Writes bookmarks as a markdown list, with a heading for every folder.
+pub fn write_markdown(root: &Folder) -> String {
+    let mut markdown = String::from("# Bookmarks\n");
+    write_markdown_folder(&mut markdown, root, 1);
+
+    markdown
+}
+
+fn write_markdown_folder(markdown: &mut String, folder: &Folder, depth: usize) {
+    if !folder.bookmarks.is_empty() {
+        markdown.push('\n');
+    }
+    for (name, bookmark) in folder.bookmarks.iter() {
+        let name = name.replace('[', "\\[").replace(']', "\\]");
+        let url = if bookmark.url.contains([' ', '(', ')']) {
+            format!("<{}>", bookmark.url)
+        } else {
+            bookmark.url.clone()
+        };
+        write!(markdown, "- [{}]({})", name, url).unwrap();
+        if let Some(description) = &bookmark.description {
+            write!(markdown, ": {}", clean_text(description)).unwrap();
+        }
+        markdown.push('\n');
+    }
+
+    // Markdown has no headings below the sixth level.
+    for (folder_name, subfolder) in folder.folders.iter() {
+        writeln!(
+            markdown,
+            "\n{} {}",
+            "#".repeat((depth + 1).min(6)),
+            folder_name
+        )
+        .unwrap();
+        write_markdown_folder(markdown, subfolder, depth + 1);
+    }
+}
+
+/// Writes bookmarks as an org list, with a heading for every folder.
+pub fn write_org(root: &Folder) -> String {
+    let mut org = String::from("#+TITLE: Bookmarks\n");
+    write_org_folder(&mut org, root, 0);
+
+    org
+}
+
+fn write_org_folder(org: &mut String, folder: &Folder, depth: usize) {
+    if !folder.bookmarks.is_empty() {
+        org.push('\n');
+    }
+    for (name, bookmark) in folder.bookmarks.iter() {
+        // Org link descriptions can't contain brackets.
+        let name = name.replace('[', "(").replace(']', ")");
+        write!(org, "- [[{}][{}]]", bookmark.url, name).unwrap();
+        if let Some(description) = &bookmark.description {
+            write!(org, ": {}", clean_text(description)).unwrap();
+        }
+        org.push('\n');
+    }
+
+    for (folder_name, subfolder) in folder.folders.iter() {
+        writeln!(org, "\n{} {}", "*".repeat(depth + 1), folder_name).unwrap();
+        write_org_folder(org, subfolder, depth + 1);
+    }
+}
@@ -0,0 +1,292 @@
+use super::launcher::{Launcher, LauncherOptions};
+use super::{
+    add_bookmark, delete_bookmark, index, normalize_bookmark_url, open_search, open_url,
+    page_details, spawn_terminal, urls, ConfigBookmark,
+};
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::path::Path;
+
+/// Names of the supported menu programs.
+pub const MENU_LAUNCHERS: &[&str] = &["dmenu", "rofi", "fuzzel", "wofi", "fzf"];
+
+const ACTION_ADD: &str = "Add bookmark";
+const ACTION_EDIT: &str = "Edit bookmark";
+const ACTION_DELETE: &str = "Delete bookmark";
+
+/// dmenu compatible program driving the menu.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuLauncher {
+    Dmenu,
+    Rofi,
+    Fuzzel,
+    Wofi,
+    Fzf,
+}
+
+impl std::str::FromStr for MenuLauncher {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Ok(match name {
+            "dmenu" => MenuLauncher::Dmenu,
+            "rofi" => MenuLauncher::Rofi,
+            "fuzzel" => MenuLauncher::Fuzzel,
+            "wofi" => MenuLauncher::Wofi,
+            "fzf" => MenuLauncher::Fzf,
+            _ => bail!("Menu launcher '{}' not supported.", name),
+        })
+    }
+}
+
+impl MenuLauncher {
+    /// Default process command of the program, `$BOOKIT_MENU_PROMPT` is replaced per prompt.
+    fn default_command(&self) -> &'static str {
+        match self {
+            MenuLauncher::Dmenu => "dmenu -i -l 20 -p \"$BOOKIT_MENU_PROMPT\"",
+            MenuLauncher::Rofi => {
+                "rofi -dmenu -i -show-icons -icon-theme bookit -p \"$BOOKIT_MENU_PROMPT\""
+            }
+            MenuLauncher::Fuzzel => {
+                "fuzzel --dmenu --icon-theme bookit --prompt \"$BOOKIT_MENU_PROMPT: \""
+            }
+            MenuLauncher::Wofi => "wofi --dmenu --allow-images --prompt \"$BOOKIT_MENU_PROMPT\"",
+            MenuLauncher::Fzf => "fzf --print-query --prompt \"$BOOKIT_MENU_PROMPT> \"",
+        }
+    }
+
+    /// Format of the bookmark rows the program understands.
+    fn rows_launcher(&self) -> Launcher {
+        match self {
+            MenuLauncher::Rofi | MenuLauncher::Fuzzel => Launcher::Rofi,
+            MenuLauncher::Wofi => Launcher::Wofi,
+            MenuLauncher::Dmenu | MenuLauncher::Fzf => Launcher::Dmenu,
+        }
+    }
+}
+
+/// Options of the menu.
+pub struct MenuOptions {
+    pub launcher: MenuLauncher,
+    /// Process command overriding the launcher's default command.
+    pub command: Option<String>,
+    pub launcher_options: LauncherOptions,
+}
+
+/// Runs the bookmark menu: select to open, add, edit, delete or search.
+pub fn run(config_path: &Path, options: &MenuOptions) -> Result<()> {
+    let index = index::load_index(config_path)?;
+
+    // Bookmark rows followed by the actions.
+    let mut rows = Vec::new();
+    let entries: Vec<&index::IndexEntry> = index.entries().iter().collect();
+    options.launcher.rows_launcher().write_entries(
+        &mut rows,
+        &entries,
+        &options.launcher_options,
+    )?;
+    for action in [ACTION_ADD, ACTION_EDIT, ACTION_DELETE].iter() {
+        writeln!(rows, "{}", action)?;
+    }
+
+    // Process selection.
+    let selection = match prompt(options, "bookit", &rows)? {
+        Some(selection) => selection,
+        None => return Ok(()),
+    };
+    if selection == ACTION_ADD {
+        menu_add(config_path, options, &index)
+    } else if selection == ACTION_EDIT {
+        if let Some(name) = select_bookmark(options, "Edit bookmark", &entries)? {
+            menu_edit(config_path, options, &name)?;
+        }
+        Ok(())
+    } else if selection == ACTION_DELETE {
+        if let Some(name) = select_bookmark(options, "Delete bookmark", &entries)? {
+            menu_delete(config_path, options, &name)?;
+        }
+        Ok(())
+    } else if let Some(entry) = find_entry(&entries, &selection) {
+        open_url(&entry.url)
+    } else if !selection.is_empty() {
+        // User defined input.
+        open_search(&selection)
+    } else {
+        Ok(())
+    }
+}
+
+/// Asks for the url, name and tags of a new bookmark and adds it.
+fn menu_add(config_path: &Path, options: &MenuOptions, index: &index::Index) -> Result<()> {
+    let url = match prompt(options, "Add bookmark", &[])? {
+        Some(url) if !url.is_empty() => url,
+        _ => return Ok(()),
+    };
+    let name = match prompt(options, "What is the name?", &[])? {
+        Some(name) => name,
+        None => return Ok(()),
+    };
+
+    let (name, page) = page_details(&url, &name);
+
+    let mut tag_rows = Vec::new();
+    for tag in page.tags.iter() {
+        writeln!(tag_rows, "{}", tag)?;
+    }
+    for (tag, _) in index.tags() {
+        if !page.tags.contains(tag) {
+            writeln!(tag_rows, "{}", tag)?;
+        }
+    }
+    let tags = match prompt(options, "Add tags separated by ','", &tag_rows)? {
+        Some(tags) => tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(String::from)
+            .collect(),
+        None => return Ok(()),
+    };
+
+    let (url, warnings) = normalize_bookmark_url(config_path, &name, &url)?;
+    for warning in warnings {
+        println!("{}", warning);
+    }
+    add_bookmark(
+        config_path,
+        &name,
+        ConfigBookmark {
+            url: url.clone(),
+            tags,
+            description: page.description,
+            ..ConfigBookmark::default()
+        },
+        false,
+    )?;
+    println!("Added bookmark '{}\t{}'.", name, urls::display(&url));
+
+    Ok(())
+}
+
+/// Edits a bookmark in a terminal, or in place for terminal launchers.
+fn menu_edit(config_path: &Path, options: &MenuOptions, name: &str) -> Result<()> {
+    let command = [
+        std::env::current_exe()?.display().to_string(),
+        String::from("--config"),
+        config_path.display().to_string(),
+        String::from("edit"),
+        String::from("--name"),
+        String::from(name),
+    ];
+
+    if options.launcher == MenuLauncher::Fzf {
+        std::process::Command::new(&command[0])
+            .args(&command[1..])
+            .status()
+            .context("Unable to edit bookmark.")?;
+
+        Ok(())
+    } else {
+        spawn_terminal(&command)
+    }
+}
+
+/// Asks for confirmation and deletes a bookmark.
+fn menu_delete(config_path: &Path, options: &MenuOptions, name: &str) -> Result<()> {
+    let confirmation = prompt(options, &format!("Delete '{}'", name), b"Yes\nNo\n")?;
+    if confirmation.as_deref() == Some("Yes") {
+        delete_bookmark(config_path, name)?;
+        println!("Deleted bookmark '{}'.", name);
+    }
+
+    Ok(())
+}
+
+/// Asks for a bookmark and returns its name.
+fn select_bookmark(
+    options: &MenuOptions,
+    prompt_text: &str,
+    entries: &[&index::IndexEntry],
+) -> Result<Option<String>> {
+    let mut rows = Vec::new();
+    options.launcher.rows_launcher().write_entries(
+        &mut rows,
+        entries,
+        &options.launcher_options,
+    )?;
+
+    Ok(prompt(options, prompt_text, &rows)?
+        .and_then(|selection| find_entry(entries, &selection))
+        .map(|entry| entry.name.clone()))
+}
+
+/// Finds the bookmark of a selected row.
+fn find_entry<'a>(
+    entries: &[&'a index::IndexEntry],
+    selection: &str,
+) -> Option<&'a index::IndexEntry> {
+    // wofi images and rofi row options aren't part of the label.
+    let label = match selection.find(":text:") {
+        Some(position) if selection.starts_with("img:") => &selection[position + 6..],
+        _ => selection,
+    };
+    let label = label.split('\0').next().unwrap_or("");
+
+    entries
+        .iter()
+        .find(|entry| label == format!("{}\t{}\t{}", entry.name, entry.tags.join(","), entry.url))
+        .copied()
+}
+
+/// Shows rows in the menu program, returning the selected row or typed input.
+///
+/// Returns `None` when the user cancelled the menu.
+fn prompt(options: &MenuOptions, prompt_text: &str, rows: &[u8]) -> Result<Option<String>> {
+    let command = options
+        .command
+        .as_deref()
+        .unwrap_or_else(|| options.launcher.default_command());
+    let mut command_parts =
+        shlex::split(command).with_context(|| format!("Invalid menu command '{}'.", command))?;
+    for command_part in command_parts.iter_mut() {
+        *command_part = command_part.replace("$BOOKIT_MENU_PROMPT", prompt_text);
+    }
+    if command_parts.is_empty() {
+        bail!("Menu command is empty.");
+    }
+    log::debug!("Menu: {:?}", command_parts);
+
+    // Run the menu.
+    let mut child = std::process::Command::new(&command_parts[0])
+        .args(&command_parts[1..])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Unable to run '{}'.", command_parts[0]))?;
+    if let Err(e) = child.stdin.take().unwrap().write_all(rows) {
+        // Menus may exit before reading every row.
+        if e.kind() != std::io::ErrorKind::BrokenPipe {
+            return Err(e.into());
+        }
+    }
+    let output = child.wait_with_output()?;
+
+    // fzf exits with 1 when the query matched nothing, which is still input.
+    let accepted = output.status.success()
+        || (options.launcher == MenuLauncher::Fzf && output.status.code() == Some(1));
+    if !accepted {
+        return Ok(None);
+    }
+
+    // fzf prints the query before the selection.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let selection = if options.launcher == MenuLauncher::Fzf && options.command.is_none() {
+        let query = lines.next().unwrap_or("");
+        lines.next().unwrap_or(query)
+    } else {
+        lines.next().unwrap_or("")
+    };
+
+    Ok(Some(String::from(selection.trim_end())))
+}
@@ -0,0 +1,93 @@
+use super::{clean_text, http};
+use anyhow::Result;
+use std::time::Duration;
+
+/// Time to wait for a page when filling in bookmark details.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest page read for its metadata.
+const FETCH_LIMIT: u64 = 2 * 1024 * 1024;
+
+/// Most tags suggested from the keywords of a page.
+const MAX_SUGGESTED_TAGS: usize = 5;
+
+/// Bookmark details found in a page.
+#[derive(Debug, Default, PartialEq)]
+pub struct PageMetadata {
+    /// OpenGraph title or `<title>` of the page.
+    pub title: Option<String>,
+    /// OpenGraph or meta description of the page.
+    pub description: Option<String>,
+    /// Tags suggested from the keywords of the page.
+    pub tags: Vec<String>,
+}
+
+/// Downloads a page and reads its metadata.
+pub fn fetch(url: &str) -> Result<PageMetadata> {
+    let response = http::agent(FETCH_TIMEOUT).get(url).call()?;
+    let body = http::read_body(response, FETCH_LIMIT)?;
+
+    Ok(parse(&String::from_utf8_lossy(&body)))
+}
+
+/// Reads the title, description and keywords of a page.
+fn parse(html: &str) -> PageMetadata {
+    let document = scraper::Html::parse_document(html);
+    let meta = |attribute: &str, names: &[&str]| -> Option<String> {
+        let selector = scraper::Selector::parse(&format!("meta[{}][content]", attribute)).unwrap();
+        document
+            .select(&selector)
+            .filter(|element| {
+                let name = element.value().attr(attribute).unwrap_or("");
+                names.iter().any(|n| name.eq_ignore_ascii_case(n))
+            })
+            .map(|element| clean_text(element.value().attr("content").unwrap_or("")))
+            .find(|text| !text.is_empty())
+    };
+
+    // Title.
+    let title_selector = scraper::Selector::parse("title").unwrap();
+    let title = meta("property", &["og:title"]).or_else(|| {
+        document
+            .select(&title_selector)
+            .next()
+            .map(|title| clean_text(&title.text().collect::<String>()))
+            .filter(|title| !title.is_empty())
+    });
+
+    // Description.
+    let description =
+        meta("property", &["og:description"]).or_else(|| meta("name", &["description"]));
+
+    // Suggested tags.
+    let mut tags: Vec<String> = Vec::new();
+    let keywords_selector = scraper::Selector::parse("meta[content]").unwrap();
+    for element in document.select(&keywords_selector) {
+        let name = element
+            .value()
+            .attr("name")
+            .or_else(|| element.value().attr("property"))
+            .unwrap_or("");
+        if !name.eq_ignore_ascii_case("keywords") && !name.eq_ignore_ascii_case("article:tag") {
+            continue;
+        }
+
+        for keyword in element.value().attr("content").unwrap_or("").split(',') {
+            let tag = keyword
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join("-")
+                .to_lowercase();
+            if !tag.is_empty() && !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags.truncate(MAX_SUGGESTED_TAGS);
+
+    PageMetadata {
+        title,
+        description,
+        tags,
+    }
+}
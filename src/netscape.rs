@@ -0,0 +1,156 @@
+use super::clean_text;
+use super::export::{escape_html, Folder};
+use super::import::ImportedBookmark;
+use chrono::TimeZone;
+use std::fmt::Write;
+
+/// Reads the bookmarks of a Netscape bookmark file, the `bookmarks.html` browsers export.
+///
+/// Folders are `<H3>` headings followed by a `<DL>` list of their bookmarks, bookmarks are `<A>`
+/// links optionally followed by a `<DD>` description.
+pub fn parse(html: &str) -> Vec<ImportedBookmark> {
+    let document = scraper::Html::parse_document(html);
+    let mut parser = Parser::default();
+    parser.walk(document.root_element());
+
+    parser.bookmarks
+}
+
+#[derive(Default)]
+struct Parser {
+    bookmarks: Vec<ImportedBookmark>,
+    /// Folders around the current list.
+    folders: Vec<String>,
+    /// Heading of the folder whose list comes next.
+    heading: Option<String>,
+    /// Whether a `<DD>` now describes the last bookmark.
+    describes_bookmark: bool,
+}
+
+impl Parser {
+    fn walk(&mut self, element: scraper::ElementRef) {
+        for child in element.children().filter_map(scraper::ElementRef::wrap) {
+            match child.value().name() {
+                "h3" => {
+                    self.heading = Some(text(child));
+                    self.describes_bookmark = false;
+                }
+                "dl" => {
+                    // Lists without a heading, like the outermost one, add no folder.
+                    let heading = self.heading.take();
+                    if let Some(heading) = &heading {
+                        self.folders.push(heading.clone());
+                    }
+                    self.describes_bookmark = false;
+                    self.walk(child);
+                    if heading.is_some() {
+                        self.folders.pop();
+                    }
+                    self.describes_bookmark = false;
+                }
+                "a" => {
+                    let attr = |name: &str| child.value().attr(name).unwrap_or("");
+                    if attr("href").is_empty() {
+                        continue;
+                    }
+
+                    self.bookmarks.push(ImportedBookmark {
+                        name: text(child),
+                        url: String::from(attr("href")),
+                        tags: attr("tags")
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|tag| !tag.is_empty())
+                            .map(String::from)
+                            .collect(),
+                        folders: self.folders.clone(),
+                        description: None,
+                        created: attr("add_date")
+                            .parse::<i64>()
+                            .ok()
+                            .and_then(|seconds| chrono::Utc.timestamp_opt(seconds, 0).single()),
+                    });
+                    self.describes_bookmark = true;
+                }
+                "dd" => {
+                    // Descriptions of bookmarks hold their text directly, the rest is walked.
+                    if self.describes_bookmark {
+                        let description: String = child
+                            .children()
+                            .filter_map(|node| node.value().as_text())
+                            .map(|text| &**text)
+                            .collect::<Vec<&str>>()
+                            .join(" ");
+                        let description = clean_text(&description);
+                        if !description.is_empty() {
+                            self.bookmarks.last_mut().unwrap().description = Some(description);
+                        }
+                        self.describes_bookmark = false;
+                    }
+                    self.walk(child);
+                }
+                _ => self.walk(child),
+            }
+        }
+    }
+}
+
+/// Text of an element with whitespace collapsed.
+fn text(element: scraper::ElementRef) -> String {
+    clean_text(&element.text().collect::<String>())
+}
+
+/// Writes bookmarks as a Netscape bookmark file that browsers can import.
+pub fn write(root: &Folder) -> String {
+    let mut html = String::from(
+        r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+"#,
+    );
+    write_folder(&mut html, root, 0);
+
+    html
+}
+
+/// Writes the list of a folder, subfolders first.
+fn write_folder(html: &mut String, folder: &Folder, depth: usize) {
+    let indent = "    ".repeat(depth);
+    writeln!(html, "{}<DL><p>", indent).unwrap();
+
+    for (folder_name, subfolder) in folder.folders.iter() {
+        writeln!(
+            html,
+            "{}    <DT><H3>{}</H3>",
+            indent,
+            escape_html(folder_name)
+        )
+        .unwrap();
+        write_folder(html, subfolder, depth + 1);
+    }
+    for (name, bookmark) in folder.bookmarks.iter() {
+        write!(
+            html,
+            "{}    <DT><A HREF=\"{}\"",
+            indent,
+            escape_html(&bookmark.url)
+        )
+        .unwrap();
+        if let Some(created) = bookmark.created {
+            write!(html, " ADD_DATE=\"{}\"", created.timestamp()).unwrap();
+        }
+        if !bookmark.tags.is_empty() {
+            write!(html, " TAGS=\"{}\"", escape_html(&bookmark.tags.join(","))).unwrap();
+        }
+        writeln!(html, ">{}</A>", escape_html(name)).unwrap();
+        if let Some(description) = &bookmark.description {
+            writeln!(html, "{}    <DD>{}", indent, escape_html(description)).unwrap();
+        }
+    }
+
+    writeln!(html, "{}</DL><p>", indent).unwrap();
+}
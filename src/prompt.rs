@@ -0,0 +1,26 @@
+use anyhow::{bail, Result};
+use std::io::{BufRead, Write};
+
+/// Asks a yes or no question, anything but `y` or `yes` is no, including no input at all.
+pub fn confirm(input: &mut dyn BufRead, out: &mut dyn Write, question: &str) -> Result<bool> {
+    write!(out, "{} [y/N]: ", question)?;
+    out.flush()?;
+
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        writeln!(out)?;
+        return Ok(false);
+    }
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Reads a trimmed answer, failing when the input ended.
+pub fn read_answer(input: &mut dyn BufRead) -> Result<String> {
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        bail!("No answer given.");
+    }
+
+    Ok(String::from(answer.trim()))
+}
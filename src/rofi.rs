@@ -0,0 +1,280 @@
+use super::{
+    add_bookmark, delete_bookmark, icons, index, normalize_bookmark_url, open_search, open_url,
+    page_details, spawn_terminal, urls, ConfigBookmark,
+};
+use anyhow::Result;
+use std::io::Write;
+use std::path::Path;
+
+/// Options of the rofi script mode.
+pub struct RofiOptions {
+    /// Include icons in bookmark rows.
+    pub include_icon: bool,
+    /// Key bound to `-kb-custom-1`, shown in the message line.
+    pub key_add: String,
+    /// Key bound to `-kb-custom-2`, shown in the message line.
+    pub key_edit: String,
+    /// Key bound to `-kb-custom-3`, shown in the message line.
+    pub key_delete: String,
+}
+
+/// Step of the menu flow, passed between invocations through `ROFI_DATA`.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "state", rename_all = "kebab-case")]
+enum RofiState {
+    Bookmarks,
+    Add,
+    AddName {
+        url: String,
+    },
+    AddTags {
+        url: String,
+        name: String,
+        /// Description of the page when the name was filled in from it.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+    },
+    Delete {
+        name: String,
+    },
+}
+
+/// Value of `ROFI_RETV` when an entry was selected.
+const ROFI_RETV_SELECTED: u32 = 1;
+/// Value of `ROFI_RETV` when custom input was accepted.
+const ROFI_RETV_CUSTOM: u32 = 2;
+/// Value of `ROFI_RETV` for `-kb-custom-1`.
+const ROFI_RETV_KB_ADD: u32 = 10;
+/// Value of `ROFI_RETV` for `-kb-custom-2`.
+const ROFI_RETV_KB_EDIT: u32 = 11;
+/// Value of `ROFI_RETV` for `-kb-custom-3`.
+const ROFI_RETV_KB_DELETE: u32 = 12;
+
+/// Runs one step of rofi's script mode protocol.
+///
+/// rofi runs the script without a selection to get the initial rows and then again with the
+/// selected row (or custom input) every time the user accepts something, with `ROFI_RETV`,
+/// `ROFI_INFO` and `ROFI_DATA` describing what happened. Printing nothing closes rofi.
+pub fn run(
+    config_path: &Path,
+    selection: Option<&str>,
+    options: &RofiOptions,
+    out: &mut dyn Write,
+) -> Result<()> {
+    // Read rofi's environment.
+    let retv: u32 = std::env::var("ROFI_RETV")
+        .ok()
+        .and_then(|retv| retv.parse().ok())
+        .unwrap_or(0);
+    let info = std::env::var("ROFI_INFO")
+        .ok()
+        .filter(|info| !info.is_empty());
+    let state = std::env::var("ROFI_DATA")
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or(RofiState::Bookmarks);
+    let selection = selection.unwrap_or("").trim();
+    log::debug!(
+        "rofi: retv={} info={:?} state={:?} selection={:?}",
+        retv,
+        info,
+        state,
+        selection
+    );
+
+    match (state, retv) {
+        // Open the selected bookmark.
+        (RofiState::Bookmarks, ROFI_RETV_SELECTED) if info.is_some() => {
+            let index = index::load_index(config_path)?;
+            let name = info.unwrap();
+            match index.entries().iter().find(|entry| entry.name == name) {
+                Some(entry) => open_url(&entry.url),
+                None => write_bookmarks(
+                    config_path,
+                    Some(&format!("Bookmark '{}' not found.", name)),
+                    options,
+                    out,
+                ),
+            }
+        }
+        // Search for anything that isn't a bookmark.
+        (RofiState::Bookmarks, ROFI_RETV_SELECTED) | (RofiState::Bookmarks, ROFI_RETV_CUSTOM) => {
+            if selection.is_empty() {
+                write_bookmarks(config_path, None, options, out)
+            } else {
+                open_search(selection)
+            }
+        }
+        (RofiState::Bookmarks, ROFI_RETV_KB_ADD) => {
+            write_mode(out, "Add bookmark", None, &RofiState::Add)?;
+            Ok(())
+        }
+        (RofiState::Bookmarks, ROFI_RETV_KB_EDIT) if info.is_some() => {
+            let name = info.unwrap();
+            spawn_terminal(&[
+                std::env::current_exe()?.display().to_string(),
+                String::from("--config"),
+                config_path.display().to_string(),
+                String::from("edit"),
+                String::from("--name"),
+                name,
+            ])
+        }
+        (RofiState::Bookmarks, ROFI_RETV_KB_DELETE) if info.is_some() => {
+            let name = info.unwrap();
+            write_mode(
+                out,
+                &format!("Delete '{}'", name),
+                None,
+                &RofiState::Delete { name },
+            )?;
+            writeln!(out, "Yes")?;
+            writeln!(out, "No")?;
+            Ok(())
+        }
+        (RofiState::Add, ROFI_RETV_SELECTED) | (RofiState::Add, ROFI_RETV_CUSTOM)
+            if !selection.is_empty() =>
+        {
+            write_mode(
+                out,
+                "What is the name?",
+                None,
+                &RofiState::AddName {
+                    url: String::from(selection),
+                },
+            )?;
+            Ok(())
+        }
+        (RofiState::AddName { url }, ROFI_RETV_SELECTED)
+        | (RofiState::AddName { url }, ROFI_RETV_CUSTOM) => {
+            let (name, page) = page_details(&url, selection);
+
+            write_mode(
+                out,
+                "Add tags separated by ','",
+                None,
+                &RofiState::AddTags {
+                    url,
+                    name,
+                    description: page.description,
+                },
+            )?;
+            for tag in page.tags.iter() {
+                writeln!(out, "{}", tag)?;
+            }
+            let index = index::load_index(config_path)?;
+            for (tag, _) in index.tags() {
+                if !page.tags.contains(tag) {
+                    writeln!(out, "{}", tag)?;
+                }
+            }
+            Ok(())
+        }
+        (
+            RofiState::AddTags {
+                url,
+                name,
+                description,
+            },
+            ROFI_RETV_SELECTED,
+        )
+        | (
+            RofiState::AddTags {
+                url,
+                name,
+                description,
+            },
+            ROFI_RETV_CUSTOM,
+        ) => {
+            let tags = selection
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(String::from)
+                .collect();
+            let (url, mut messages) = normalize_bookmark_url(config_path, &name, &url)?;
+            messages.push(
+                match add_bookmark(
+                    config_path,
+                    &name,
+                    ConfigBookmark {
+                        url: url.clone(),
+                        tags,
+                        description,
+                        ..ConfigBookmark::default()
+                    },
+                    false,
+                ) {
+                    Ok(()) => format!("Added bookmark '{}\t{}'.", name, urls::display(&url)),
+                    Err(e) => e.to_string(),
+                },
+            );
+            let message = messages.join(" ");
+            write_bookmarks(config_path, Some(&message), options, out)
+        }
+        (RofiState::Delete { name }, ROFI_RETV_SELECTED) if selection == "Yes" => {
+            let message = match delete_bookmark(config_path, &name) {
+                Ok(()) => format!("Deleted bookmark '{}'.", name),
+                Err(e) => e.to_string(),
+            };
+            write_bookmarks(config_path, Some(&message), options, out)
+        }
+        // Anything else goes back to the bookmarks.
+        _ => write_bookmarks(config_path, None, options, out),
+    }
+}
+
+/// Writes the bookmark rows.
+fn write_bookmarks(
+    config_path: &Path,
+    message: Option<&str>,
+    options: &RofiOptions,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let index = index::load_index(config_path)?;
+
+    let keys = format!(
+        "{}: add, {}: edit, {}: delete",
+        options.key_add, options.key_edit, options.key_delete
+    );
+    write_mode(
+        out,
+        "bookit",
+        Some(message.unwrap_or(&keys)),
+        &RofiState::Bookmarks,
+    )?;
+
+    for entry in index.entries() {
+        write!(
+            out,
+            "{}\t{}\t{}\0info\x1f{}",
+            entry.name,
+            entry.tags.join(","),
+            entry.url,
+            entry.name
+        )?;
+        if options.include_icon {
+            write!(out, "\x1ficon\x1f{}", icons::entry_icon(entry))?;
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the mode options of a menu step.
+fn write_mode(
+    out: &mut dyn Write,
+    prompt: &str,
+    message: Option<&str>,
+    state: &RofiState,
+) -> Result<()> {
+    writeln!(out, "\0prompt\x1f{}", prompt)?;
+    if let Some(message) = message {
+        writeln!(out, "\0message\x1f{}", message)?;
+    }
+    writeln!(out, "\0use-hot-keys\x1ftrue")?;
+    writeln!(out, "\0data\x1f{}", serde_json::to_string(state)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use super::export::{escape_html, Folder};
+use super::icons;
+use super::index::Index;
+use super::urls;
+use base64::Engine;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Schemes of urls that are linked, others like `javascript:` or `data:` are shown as text.
+const LINK_SCHEMES: &[&str] = &["http", "https", "file", "mailto"];
+
+/// Style of the start page.
+const STYLE: &str = r#"
+:root { color-scheme: light dark; --accent: #0a84ff; }
+* { box-sizing: border-box; }
+body { margin: 0 auto; max-width: 72rem; padding: 2rem; font: 15px/1.4 system-ui, sans-serif; }
+input { width: 100%; padding: .75rem 1rem; font-size: 1.1rem; border: 1px solid #8886; border-radius: .5rem; background: transparent; color: inherit; }
+input:focus { outline: 2px solid var(--accent); }
+main { columns: 18rem; column-gap: 2rem; margin-top: 2rem; }
+section { break-inside: avoid; margin-bottom: 1.5rem; }
+h2 { font-size: .85rem; text-transform: uppercase; letter-spacing: .05em; opacity: .6; margin: 0 0 .5rem; }
+ul { list-style: none; margin: 0; padding: 0; }
+a, .link { display: flex; align-items: center; gap: .5rem; padding: .25rem .5rem; border-radius: .25rem; color: inherit; text-decoration: none; }
+a:hover, a:focus, li.first a { background: #8882; }
+.icon { flex: none; width: 16px; height: 16px; background-size: contain; }
+.initial { display: inline-flex; align-items: center; justify-content: center; border-radius: 50%; background: #8884; font-size: 10px; }
+[hidden] { display: none; }
+"#;
+
+/// Script filtering the bookmarks while typing, `Enter` opens the first match.
+const SCRIPT: &str = r#"
+const search = document.getElementById("search");
+const items = Array.from(document.querySelectorAll("li"));
+const sections = Array.from(document.querySelectorAll("section"));
+function filter() {
+  const terms = search.value.toLowerCase().split(/\s+/).filter(Boolean);
+  let first = null;
+  for (const item of items) {
+    const visible = terms.every(term => item.dataset.search.includes(term));
+    item.hidden = !visible;
+    item.classList.remove("first");
+    if (visible && !first && terms.length && item.querySelector("a")) first = item;
+  }
+  if (first) first.classList.add("first");
+  for (const section of sections) {
+    section.hidden = !section.querySelector("li:not([hidden])");
+  }
+  return first;
+}
+search.addEventListener("input", filter);
+search.addEventListener("keydown", event => {
+  const first = filter();
+  if (event.key === "Enter" && first) window.location.href = first.querySelector("a").href;
+});
+"#;
+
+/// Icons of bookmarks as data urls by bookmark name, for bookmarks whose icon is in the icon theme.
+pub fn icon_data_urls(index: &Index, icons_path: &Path) -> BTreeMap<String, String> {
+    let mut data_urls = BTreeMap::new();
+    for entry in index.entries() {
+        let icon_path = icons::entry_icon_path(icons_path, entry);
+        let data = match std::fs::read(&icon_path) {
+            Ok(data) => data,
+            Err(_) => continue,
+        };
+        let extension = icon_path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        let media_type = match extension.as_deref() {
+            Some("svg") => "image/svg+xml",
+            Some("jpg") | Some("jpeg") => "image/jpeg",
+            Some("gif") => "image/gif",
+            Some("webp") => "image/webp",
+            Some("ico") => "image/x-icon",
+            _ => "image/png",
+        };
+        data_urls.insert(
+            entry.name.clone(),
+            format!(
+                "data:{};base64,{}",
+                media_type,
+                base64::engine::general_purpose::STANDARD.encode(data)
+            ),
+        );
+    }
+
+    data_urls
+}
+
+/// Writes bookmarks as a single HTML page to use as a browser start page.
+///
+/// Every folder is a section of links with their icons inlined, typing filters the links and
+/// `Enter` opens the first one. Bookmarks sharing an icon share a style rule.
+pub fn write(root: &Folder, icons: &BTreeMap<String, String>) -> String {
+    // Sections by folder path, the bookmarks without a folder first.
+    let mut sections = Vec::new();
+    collect_sections(root, &mut Vec::new(), &mut sections);
+
+    // One style rule per distinct icon.
+    let mut icon_classes: BTreeMap<&str, usize> = BTreeMap::new();
+    for data_url in icons.values() {
+        let next_class = icon_classes.len();
+        icon_classes.entry(data_url.as_str()).or_insert(next_class);
+    }
+    let mut icon_style = String::new();
+    for (data_url, class) in icon_classes.iter() {
+        writeln!(
+            icon_style,
+            ".icon-{} {{ background-image: url(\"{}\"); }}",
+            class, data_url
+        )
+        .unwrap();
+    }
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html>").unwrap();
+    writeln!(html, "<head>").unwrap();
+    writeln!(html, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(
+        html,
+        "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+    )
+    .unwrap();
+    writeln!(html, "<title>Bookmarks</title>").unwrap();
+    writeln!(html, "<style>{}{}</style>", STYLE, icon_style).unwrap();
+    writeln!(html, "</head>").unwrap();
+    writeln!(html, "<body>").unwrap();
+    writeln!(
+        html,
+        "<input id=\"search\" type=\"search\" placeholder=\"Search bookmarks\" autofocus>"
+    )
+    .unwrap();
+    writeln!(html, "<main>").unwrap();
+    for (path, folder) in sections {
+        writeln!(html, "<section>").unwrap();
+        if !path.is_empty() {
+            writeln!(html, "<h2>{}</h2>", escape_html(&path.join(" / "))).unwrap();
+        }
+        writeln!(html, "<ul>").unwrap();
+        for (name, bookmark) in folder.bookmarks.iter() {
+            let search = [
+                name,
+                bookmark.url.as_str(),
+                &bookmark.tags.join(" "),
+                bookmark.description.as_deref().unwrap_or(""),
+                &path.join(" "),
+            ]
+            .join(" ")
+            .to_lowercase();
+            let icon = match icons.get(*name) {
+                Some(data_url) => format!(
+                    "<span class=\"icon icon-{}\"></span>",
+                    icon_classes[data_url.as_str()]
+                ),
+                None => format!(
+                    "<span class=\"icon initial\">{}</span>",
+                    escape_html(&name.chars().take(1).collect::<String>().to_uppercase())
+                ),
+            };
+            let linked = urls::parse(&bookmark.url)
+                .map(|url| LINK_SCHEMES.contains(&url.scheme()))
+                .unwrap_or(false);
+            write!(html, "<li data-search=\"{}\">", escape_html(&search)).unwrap();
+            if linked {
+                write!(html, "<a href=\"{}\"", escape_html(&bookmark.url)).unwrap();
+            } else {
+                write!(html, "<span class=\"link\"").unwrap();
+            }
+            if let Some(description) = &bookmark.description {
+                write!(html, " title=\"{}\"", escape_html(description)).unwrap();
+            }
+            writeln!(
+                html,
+                ">{}{}</{}></li>",
+                icon,
+                escape_html(name),
+                if linked { "a" } else { "span" }
+            )
+            .unwrap();
+        }
+        writeln!(html, "</ul>").unwrap();
+        writeln!(html, "</section>").unwrap();
+    }
+    writeln!(html, "</main>").unwrap();
+    writeln!(html, "<script>{}</script>", SCRIPT).unwrap();
+    writeln!(html, "</body>").unwrap();
+    writeln!(html, "</html>").unwrap();
+
+    html
+}
+
+/// Folders with bookmarks, outermost first, with their path.
+fn collect_sections<'a, 'b>(
+    folder: &'b Folder<'a>,
+    path: &mut Vec<String>,
+    sections: &mut Vec<(Vec<String>, &'b Folder<'a>)>,
+) {
+    if !folder.bookmarks.is_empty() {
+        sections.push((path.clone(), folder));
+    }
+    for (folder_name, subfolder) in folder.folders.iter() {
+        path.push(folder_name.clone());
+        collect_sections(subfolder, path, sections);
+        path.pop();
+    }
+}
@@ -0,0 +1,215 @@
+use super::import::ImportedBookmark;
+use anyhow::{bail, Context, Result};
+use chrono::TimeZone;
+use std::collections::BTreeMap;
+
+/// Bookmark fields that columns can be mapped to.
+pub const FIELDS: &[&str] = &["name", "url", "tags", "description", "folder", "created"];
+
+/// Columns the bookmark fields are read from.
+///
+/// Parsed from `field=column` pairs like `name=title,url=href`, fields that aren't mapped are read
+/// from the column of the same name. JSON columns can name nested values, like
+/// `folder=collection.title`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldMap {
+    columns: BTreeMap<&'static str, String>,
+}
+
+impl Default for FieldMap {
+    fn default() -> Self {
+        FieldMap {
+            columns: FIELDS
+                .iter()
+                .map(|field| (*field, String::from(*field)))
+                .collect(),
+        }
+    }
+}
+
+impl std::str::FromStr for FieldMap {
+    type Err = anyhow::Error;
+
+    fn from_str(map: &str) -> Result<Self> {
+        let mut field_map = FieldMap::default();
+        for pair in map.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (field, column) = match pair.split_once('=') {
+                Some((field, column)) if !column.trim().is_empty() => (field.trim(), column.trim()),
+                _ => bail!("Invalid mapping '{}', expected 'field=column'.", pair),
+            };
+            let field = match FIELDS.iter().find(|known_field| **known_field == field) {
+                Some(field) => *field,
+                None => bail!(
+                    "Field '{}' not supported, expected one of {}.",
+                    field,
+                    FIELDS.join(", ")
+                ),
+            };
+            field_map.columns.insert(field, String::from(column));
+        }
+
+        Ok(field_map)
+    }
+}
+
+/// Options of CSV and JSON imports.
+pub struct TabularOptions {
+    pub map: FieldMap,
+    /// Separator of the tags of a tags column.
+    pub tag_separator: String,
+}
+
+/// Value of a column.
+enum Value {
+    Text(String),
+    List(Vec<String>),
+}
+
+/// Reads bookmarks from the rows of a CSV file with a header.
+pub fn parse_csv(contents: &str, options: &TabularOptions) -> Result<Vec<ImportedBookmark>> {
+    check_options(options)?;
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .context("Unable to read the CSV header.")?
+        .iter()
+        .map(|header| String::from(header.trim()))
+        .collect();
+
+    let mut bookmarks = Vec::new();
+    for (position, record) in reader.records().enumerate() {
+        let record = record.with_context(|| format!("Unable to read CSV row {}.", position + 2))?;
+        let row: BTreeMap<&str, &str> = headers
+            .iter()
+            .map(String::as_str)
+            .zip(record.iter())
+            .collect();
+        bookmarks.extend(bookmark(
+            |column| {
+                row.get(column)
+                    .map(|value| Value::Text(String::from(*value)))
+            },
+            options,
+        ));
+    }
+
+    Ok(bookmarks)
+}
+
+/// Reads bookmarks from the objects of a JSON array.
+///
+/// The array can also be the first array of a JSON object, like the `items` of `{"items": [...]}`.
+pub fn parse_json(contents: &str, options: &TabularOptions) -> Result<Vec<ImportedBookmark>> {
+    check_options(options)?;
+    let json: serde_json::Value = serde_json::from_str(contents).context("Invalid JSON.")?;
+    let items = match &json {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(object) => {
+            match object.values().find_map(|value| value.as_array()) {
+                Some(items) => items,
+                None => bail!("No array of bookmarks found in the JSON object."),
+            }
+        }
+        _ => bail!("Expected a JSON array of bookmarks."),
+    };
+
+    let mut bookmarks = Vec::new();
+    for item in items {
+        bookmarks.extend(bookmark(
+            |column| json_value(item.pointer(&json_pointer(column))?),
+            options,
+        ));
+    }
+
+    Ok(bookmarks)
+}
+
+/// Fails for options no rows can be read with.
+fn check_options(options: &TabularOptions) -> Result<()> {
+    if options.tag_separator.is_empty() {
+        bail!("Tag separator can't be empty.");
+    }
+
+    Ok(())
+}
+
+/// JSON pointer of a column, with a segment for every `.` separated key.
+fn json_pointer(column: &str) -> String {
+    column
+        .split('.')
+        .map(|key| format!("/{}", key.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Column value of a JSON value, nothing for `null` and objects.
+fn json_value(value: &serde_json::Value) -> Option<Value> {
+    let text = |value: &serde_json::Value| match value {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Number(number) => Some(number.to_string()),
+        serde_json::Value::Bool(boolean) => Some(boolean.to_string()),
+        _ => None,
+    };
+
+    match value {
+        serde_json::Value::Array(values) => {
+            Some(Value::List(values.iter().filter_map(text).collect()))
+        }
+        value => text(value).map(Value::Text),
+    }
+}
+
+/// Bookmark of a row, nothing when the row has no url.
+fn bookmark(
+    value: impl Fn(&str) -> Option<Value>,
+    options: &TabularOptions,
+) -> Option<ImportedBookmark> {
+    let text = |field: &str| match value(&options.map.columns[field]) {
+        Some(Value::Text(text)) => String::from(text.trim()),
+        Some(Value::List(values)) => values.join(&options.tag_separator),
+        None => String::new(),
+    };
+
+    let url = text("url");
+    if url.is_empty() {
+        log::debug!("Skipped row without a url.");
+        return None;
+    }
+
+    let tags = match value(&options.map.columns["tags"]) {
+        Some(Value::Text(text)) => text
+            .split(options.tag_separator.as_str())
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(String::from)
+            .collect(),
+        Some(Value::List(values)) => values,
+        None => Vec::new(),
+    };
+    let description = text("description");
+
+    Some(ImportedBookmark {
+        name: text("name"),
+        url,
+        tags,
+        folders: text("folder")
+            .split('/')
+            .map(str::trim)
+            .filter(|folder_name| !folder_name.is_empty())
+            .map(String::from)
+            .collect(),
+        description: Some(description).filter(|description| !description.is_empty()),
+        created: parse_time(&text("created")),
+    })
+}
+
+/// Time of an RFC 3339 date or of seconds since the epoch.
+fn parse_time(text: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&chrono::Utc));
+    }
+    text.parse::<i64>()
+        .ok()
+        .and_then(|seconds| chrono::Utc.timestamp_opt(seconds, 0).single())
+}
@@ -0,0 +1,720 @@
+use super::{
+    add_bookmark, delete_bookmark, load_config, normalize_bookmark_url, open_url, page_details,
+    urls, Config, ConfigBookmark,
+};
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+type TuiTerminal = Terminal<CrosstermBackend<std::io::Stdout>>;
+
+const KEYS_BROWSE: &str =
+    "enter: open  tab: tags  ^a: add  ^e: edit  ^d: delete  ^t: retag  esc: clear/quit";
+
+/// Pane receiving the arrow keys.
+#[derive(Debug, PartialEq)]
+enum Focus {
+    Bookmarks,
+    Tags,
+}
+
+/// What typed text is for.
+#[derive(Debug)]
+enum Input {
+    AddUrl,
+    AddName {
+        url: String,
+    },
+    AddTags {
+        url: String,
+        name: String,
+        description: Option<String>,
+    },
+    Retag {
+        name: String,
+    },
+}
+
+/// Interaction the keys are routed to.
+#[derive(Debug)]
+enum Mode {
+    Browse,
+    Input {
+        input: Input,
+        prompt: String,
+        value: String,
+    },
+    ConfirmDelete {
+        name: String,
+    },
+}
+
+/// Terminal user interface state.
+struct App {
+    config_path: PathBuf,
+    config: Config,
+    matcher: SkimMatcherV2,
+    query: String,
+    tags: Vec<(String, usize)>,
+    tag_filter: Option<String>,
+    bookmarks: Vec<String>,
+    bookmarks_state: ListState,
+    tags_state: ListState,
+    focus: Focus,
+    mode: Mode,
+    message: Option<String>,
+    quit: bool,
+}
+
+/// Runs the terminal user interface until the user quits.
+pub fn run(config_path: &Path) -> Result<()> {
+    // Load before taking over the terminal so errors are printed normally.
+    let mut app = App::new(config_path)?;
+
+    let mut terminal = enter_terminal()?;
+    let result = app.run(&mut terminal);
+    leave_terminal(&mut terminal)?;
+
+    result
+}
+
+fn enter_terminal() -> Result<TuiTerminal> {
+    enable_raw_mode()?;
+    crossterm::execute!(std::io::stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
+    terminal.clear()?;
+
+    Ok(terminal)
+}
+
+fn leave_terminal(terminal: &mut TuiTerminal) -> Result<()> {
+    disable_raw_mode()?;
+    crossterm::execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    Ok(())
+}
+
+impl App {
+    fn new(config_path: &Path) -> Result<App> {
+        Ok(App::with_config(config_path, load_config(config_path)?))
+    }
+
+    fn with_config(config_path: &Path, config: Config) -> App {
+        let mut app = App {
+            config_path: PathBuf::from(config_path),
+            config,
+            matcher: SkimMatcherV2::default(),
+            query: String::new(),
+            tags: Vec::new(),
+            tag_filter: None,
+            bookmarks: Vec::new(),
+            bookmarks_state: ListState::default(),
+            tags_state: ListState::default(),
+            focus: Focus::Bookmarks,
+            mode: Mode::Browse,
+            message: None,
+            quit: false,
+        };
+        app.refresh();
+
+        app
+    }
+
+    fn run(&mut self, terminal: &mut TuiTerminal) -> Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key, terminal)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reloads the configuration after it was written, keeping the old one when it can't be read.
+    fn reload(&mut self) {
+        match load_config(&self.config_path) {
+            Ok(config) => {
+                self.config = config;
+                self.refresh();
+            }
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+
+    /// Recomputes the tags and the filtered bookmarks.
+    fn refresh(&mut self) {
+        // Tags.
+        let mut tags: BTreeMap<&String, usize> = BTreeMap::new();
+        for bookmark in self.config.bookmarks.values() {
+            for tag in bookmark.tags.iter() {
+                *tags.entry(tag).or_default() += 1;
+            }
+        }
+        self.tags = tags
+            .into_iter()
+            .map(|(tag, count)| (tag.clone(), count))
+            .collect();
+        if let Some(tag_filter) = &self.tag_filter {
+            if !self.tags.iter().any(|(tag, _)| tag == tag_filter) {
+                self.tag_filter = None;
+            }
+        }
+
+        // Bookmarks matching the tag and the fuzzy query, best matches first.
+        let mut bookmarks: Vec<(i64, &String)> = self
+            .config
+            .bookmarks
+            .iter()
+            .filter(|(_, bookmark)| match &self.tag_filter {
+                Some(tag_filter) => bookmark.tags.contains(tag_filter),
+                None => true,
+            })
+            .filter_map(|(name, bookmark)| {
+                if self.query.is_empty() {
+                    return Some((0, name));
+                }
+                let haystack = format!("{} {} {}", name, bookmark.tags.join(" "), bookmark.url);
+                self.matcher
+                    .fuzzy_match(&haystack, &self.query)
+                    .map(|score| (score, name))
+            })
+            .collect();
+        bookmarks.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        self.bookmarks = bookmarks
+            .into_iter()
+            .map(|(_, name)| name.clone())
+            .collect();
+
+        // Keep the selection inside the list.
+        let selected = match self.bookmarks_state.selected() {
+            _ if self.bookmarks.is_empty() => None,
+            Some(selected) => Some(selected.min(self.bookmarks.len() - 1)),
+            None => Some(0),
+        };
+        self.bookmarks_state.select(selected);
+        if self.tags_state.selected().is_none() {
+            self.tags_state.select(Some(0));
+        }
+    }
+
+    fn selected_bookmark(&self) -> Option<(&String, &ConfigBookmark)> {
+        let name = self.bookmarks.get(self.bookmarks_state.selected()?)?;
+        self.config.bookmarks.get_key_value(name)
+    }
+
+    /// Routes a key to the current mode.
+    ///
+    /// Failed actions are reported in the status line, only terminal errors end the interface.
+    fn handle_key(&mut self, key: KeyEvent, terminal: &mut TuiTerminal) -> Result<()> {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        if control && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return Ok(());
+        }
+
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => self.handle_browse_key(key, control, terminal)?,
+            Mode::Input {
+                input,
+                prompt,
+                mut value,
+            } => match key.code {
+                KeyCode::Enter => self.submit(input, value),
+                KeyCode::Esc => self.message = None,
+                KeyCode::Backspace => {
+                    value.pop();
+                    self.mode = Mode::Input {
+                        input,
+                        prompt,
+                        value,
+                    };
+                }
+                KeyCode::Char(c) => {
+                    value.push(c);
+                    self.mode = Mode::Input {
+                        input,
+                        prompt,
+                        value,
+                    };
+                }
+                _ => {
+                    self.mode = Mode::Input {
+                        input,
+                        prompt,
+                        value,
+                    }
+                }
+            },
+            Mode::ConfirmDelete { name } => {
+                if key.code == KeyCode::Char('y') {
+                    self.message = Some(match delete_bookmark(&self.config_path, &name) {
+                        Ok(()) => format!("Deleted bookmark '{}'.", name),
+                        Err(e) => e.to_string(),
+                    });
+                    self.reload();
+                } else {
+                    self.message = None;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_browse_key(
+        &mut self,
+        key: KeyEvent,
+        control: bool,
+        terminal: &mut TuiTerminal,
+    ) -> Result<()> {
+        match key.code {
+            KeyCode::Char('a') if control => {
+                self.prompt(Input::AddUrl, "Url", String::new());
+            }
+            KeyCode::Char('e') if control => {
+                if let Some((name, _)) = self.selected_bookmark() {
+                    let name = name.clone();
+                    self.edit(&name, terminal)?;
+                }
+            }
+            KeyCode::Char('d') if control => {
+                if let Some((name, _)) = self.selected_bookmark() {
+                    let name = name.clone();
+                    self.message = Some(format!("Delete '{}'? (y/n)", name));
+                    self.mode = Mode::ConfirmDelete { name };
+                }
+            }
+            KeyCode::Char('t') if control => {
+                if let Some((name, bookmark)) = self.selected_bookmark() {
+                    let (name, tags) = (name.clone(), bookmark.tags.join(","));
+                    self.prompt(Input::Retag { name }, "Tags separated by ','", tags);
+                }
+            }
+            KeyCode::Char(c) if !control => {
+                self.query.push(c);
+                self.bookmarks_state.select(Some(0));
+                self.refresh();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refresh();
+            }
+            KeyCode::Esc => {
+                if self.query.is_empty() && self.tag_filter.is_none() {
+                    self.quit = true;
+                } else {
+                    self.query.clear();
+                    self.tag_filter = None;
+                    self.tags_state.select(Some(0));
+                    self.refresh();
+                }
+            }
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Bookmarks => Focus::Tags,
+                    Focus::Tags => Focus::Bookmarks,
+                };
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let (state, length) = match self.focus {
+                    Focus::Bookmarks => (&mut self.bookmarks_state, self.bookmarks.len()),
+                    // The first row shows every bookmark.
+                    Focus::Tags => (&mut self.tags_state, self.tags.len() + 1),
+                };
+                if length > 0 {
+                    let selected = state.selected().unwrap_or(0);
+                    state.select(Some(if key.code == KeyCode::Up {
+                        selected.saturating_sub(1)
+                    } else {
+                        (selected + 1).min(length - 1)
+                    }));
+                }
+            }
+            KeyCode::Enter => match self.focus {
+                Focus::Bookmarks => {
+                    if let Some((_, bookmark)) = self.selected_bookmark() {
+                        self.message = Some(match open_url(&bookmark.url) {
+                            Ok(()) => format!("Opened '{}'.", bookmark.url),
+                            Err(e) => e.to_string(),
+                        });
+                    }
+                }
+                Focus::Tags => {
+                    self.tag_filter = match self.tags_state.selected() {
+                        Some(0) | None => None,
+                        Some(selected) => self.tags.get(selected - 1).map(|(tag, _)| tag.clone()),
+                    };
+                    self.focus = Focus::Bookmarks;
+                    self.bookmarks_state.select(Some(0));
+                    self.refresh();
+                }
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn prompt(&mut self, input: Input, prompt: &str, value: String) {
+        self.message = None;
+        self.mode = Mode::Input {
+            input,
+            prompt: String::from(prompt),
+            value,
+        };
+    }
+
+    /// Moves the add and retag flows forward with the typed value.
+    fn submit(&mut self, input: Input, value: String) {
+        let value = String::from(value.trim());
+
+        match input {
+            Input::AddUrl if !value.is_empty() => {
+                self.prompt(Input::AddName { url: value }, "Name", String::new());
+            }
+            Input::AddUrl => {}
+            Input::AddName { url } => {
+                let (name, page) = page_details(&url, &value);
+                self.prompt(
+                    Input::AddTags {
+                        url,
+                        name,
+                        description: page.description,
+                    },
+                    "Tags separated by ','",
+                    page.tags.join(", "),
+                );
+            }
+            Input::AddTags {
+                url,
+                name,
+                description,
+            } => {
+                let (url, mut messages) =
+                    match normalize_bookmark_url(&self.config_path, &name, &url) {
+                        Ok(normalized) => normalized,
+                        Err(e) => {
+                            self.message = Some(e.to_string());
+                            return;
+                        }
+                    };
+                let bookmark = ConfigBookmark {
+                    url: url.clone(),
+                    tags: split_tags(&value),
+                    description,
+                    ..ConfigBookmark::default()
+                };
+                messages.push(
+                    match add_bookmark(&self.config_path, &name, bookmark, false) {
+                        Ok(()) => format!("Added bookmark '{}\t{}'.", name, urls::display(&url)),
+                        Err(e) => e.to_string(),
+                    },
+                );
+                self.message = Some(messages.join(" "));
+                self.reload();
+            }
+            Input::Retag { name } => {
+                let bookmark =
+                    load_config(&self.config_path).map(|mut config| config.bookmarks.remove(&name));
+                self.message = Some(match bookmark {
+                    Ok(Some(mut bookmark)) => {
+                        bookmark.tags = split_tags(&value);
+                        match add_bookmark(&self.config_path, &name, bookmark, true) {
+                            Ok(()) => format!("Retagged bookmark '{}'.", name),
+                            Err(e) => e.to_string(),
+                        }
+                    }
+                    Ok(None) => format!("Bookmark '{}' not found.", name),
+                    Err(e) => e.to_string(),
+                });
+                self.reload();
+            }
+        }
+    }
+
+    /// Suspends the interface to edit a bookmark with `bookit edit`.
+    fn edit(&mut self, name: &str, terminal: &mut TuiTerminal) -> Result<()> {
+        leave_terminal(terminal)?;
+        let status = std::env::current_exe()
+            .and_then(|bookit| {
+                std::process::Command::new(bookit)
+                    .arg("--config")
+                    .arg(&self.config_path)
+                    .arg("edit")
+                    .arg("--name")
+                    .arg(name)
+                    .status()
+            })
+            .context("Unable to edit bookmark.");
+        *terminal = enter_terminal()?;
+
+        self.message = Some(match status {
+            Ok(status) if status.success() => format!("Edited bookmark '{}'.", name),
+            Ok(_) => format!("Unable to edit bookmark '{}'.", name),
+            Err(e) => e.to_string(),
+        });
+        self.reload();
+
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(3),
+                Constraint::Length(1),
+            ])
+            .split(frame.size());
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Percentage(45),
+                Constraint::Percentage(35),
+            ])
+            .split(rows[1]);
+        let highlight_style = Style::default().add_modifier(Modifier::REVERSED);
+        let current_focus = &self.focus;
+        let focused = |focus: Focus| {
+            if *current_focus == focus {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            }
+        };
+
+        // Input line.
+        let (title, input) = match &self.mode {
+            Mode::Input { prompt, value, .. } => (prompt.clone(), value.clone()),
+            _ => (
+                match &self.tag_filter {
+                    Some(tag) => format!("Filter [{}]", tag),
+                    None => String::from("Filter"),
+                },
+                self.query.clone(),
+            ),
+        };
+        frame.render_widget(
+            Paragraph::new(input).block(Block::default().borders(Borders::ALL).title(title)),
+            rows[0],
+        );
+
+        // Tag sidebar.
+        let mut tag_items = vec![ListItem::new(format!(
+            "All ({})",
+            self.config.bookmarks.len()
+        ))];
+        tag_items.extend(
+            self.tags
+                .iter()
+                .map(|(tag, count)| ListItem::new(format!("{} ({})", tag, count))),
+        );
+        frame.render_stateful_widget(
+            List::new(tag_items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Tags")
+                        .border_style(focused(Focus::Tags)),
+                )
+                .highlight_style(highlight_style),
+            columns[0],
+            &mut self.tags_state,
+        );
+
+        // Bookmarks.
+        let bookmark_items: Vec<ListItem> = self
+            .bookmarks
+            .iter()
+            .map(|name| ListItem::new(name.as_str()))
+            .collect();
+        frame.render_stateful_widget(
+            List::new(bookmark_items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(
+                            "Bookmarks ({}/{})",
+                            self.bookmarks.len(),
+                            self.config.bookmarks.len()
+                        ))
+                        .border_style(focused(Focus::Bookmarks)),
+                )
+                .highlight_style(highlight_style),
+            columns[1],
+            &mut self.bookmarks_state,
+        );
+
+        // Preview of every field of the selected bookmark.
+        let preview = match self.selected_bookmark() {
+            Some((name, bookmark)) => format!(
+                "{}\n\n{}",
+                name,
+                serde_yaml::to_string(bookmark)
+                    .unwrap_or_default()
+                    .trim_start_matches("---\n")
+            ),
+            None => String::new(),
+        };
+        frame.render_widget(
+            Paragraph::new(preview)
+                .wrap(Wrap { trim: false })
+                .block(Block::default().borders(Borders::ALL).title("Preview")),
+            columns[2],
+        );
+
+        // Status line.
+        let status = match (&self.message, &self.mode) {
+            (Some(message), _) => message.as_str(),
+            (None, Mode::Input { .. }) => "enter: accept  esc: cancel",
+            (None, _) => KEYS_BROWSE,
+        };
+        frame.render_widget(Paragraph::new(status), rows[2]);
+    }
+}
+
+/// Splits a comma separated list of tags.
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> Config {
+        let bookmark = |url: &str, tags: &[&str]| ConfigBookmark {
+            url: String::from(url),
+            tags: tags.iter().map(|tag| String::from(*tag)).collect(),
+            ..ConfigBookmark::default()
+        };
+        Config {
+            bookmarks: BTreeMap::from([
+                (
+                    String::from("GitHub"),
+                    bookmark("https://github.com", &["dev", "git"]),
+                ),
+                (
+                    String::from("Rust"),
+                    bookmark("https://www.rust-lang.org", &["dev"]),
+                ),
+                (
+                    String::from("Wikipedia"),
+                    bookmark("https://wikipedia.org", &["reference"]),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_app_filter_query() {
+        // Given
+        let mut app = App::with_config(Path::new("bookit.yaml"), config());
+
+        // When
+        app.query = String::from("rust");
+        app.refresh();
+
+        // Then
+        assert_eq!(app.bookmarks, vec![String::from("Rust")]);
+        assert_eq!(app.selected_bookmark().unwrap().0, "Rust");
+    }
+
+    #[test]
+    fn test_app_filter_tag() {
+        // Given
+        let mut app = App::with_config(Path::new("bookit.yaml"), config());
+        assert_eq!(
+            app.tags,
+            vec![
+                (String::from("dev"), 2),
+                (String::from("git"), 1),
+                (String::from("reference"), 1),
+            ]
+        );
+
+        // When
+        app.tag_filter = Some(String::from("dev"));
+        app.refresh();
+
+        // Then
+        assert_eq!(
+            app.bookmarks,
+            vec![String::from("GitHub"), String::from("Rust")]
+        );
+    }
+
+    #[test]
+    fn test_app_retag() {
+        // Given
+        let directory = tempfile::tempdir().unwrap();
+        let config_path = directory.path().join("bookit.yaml");
+        super::super::save_config(&config_path, &config()).unwrap();
+        let mut app = App::with_config(&config_path, config());
+        app.tag_filter = Some(String::from("git"));
+        app.refresh();
+
+        // When
+        app.submit(
+            Input::Retag {
+                name: String::from("GitHub"),
+            },
+            String::from(" code, dev ,"),
+        );
+
+        // Then
+        assert_eq!(app.message.as_deref(), Some("Retagged bookmark 'GitHub'."));
+        assert_eq!(
+            app.config.bookmarks["GitHub"].tags,
+            vec![String::from("code"), String::from("dev")]
+        );
+        assert_eq!(
+            load_config(&config_path).unwrap().bookmarks["GitHub"].tags,
+            vec![String::from("code"), String::from("dev")]
+        );
+        // The filtered tag is gone, so every bookmark is shown again.
+        assert_eq!(app.tag_filter, None);
+        assert_eq!(app.bookmarks.len(), 3);
+    }
+
+    #[test]
+    fn test_app_retag_missing_config() {
+        // Given
+        let directory = tempfile::tempdir().unwrap();
+        let mut app = App::with_config(&directory.path().join("bookit.yaml"), config());
+
+        // When
+        app.submit(
+            Input::Retag {
+                name: String::from("GitHub"),
+            },
+            String::from("code"),
+        );
+
+        // Then
+        assert!(app.message.unwrap().starts_with("No config found at"));
+        assert_eq!(app.config, config());
+    }
+}
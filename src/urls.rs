@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+
+/// Parses the url of a bookmark.
+pub fn parse(url: &str) -> Result<url::Url> {
+    url::Url::parse(url.trim()).with_context(|| format!("Invalid url '{}'.", url))
+}
+
+/// Lowercase hostname of a url without credentials, port or IPv6 brackets.
+///
+/// `mailto:` urls use the domain of their first address, schemes without a host like `tel:` or
+/// local `file:` urls have none.
+pub fn hostname(url: &url::Url) -> Option<String> {
+    let hostname = match url.host_str() {
+        Some(host) => host.trim_start_matches('[').trim_end_matches(']'),
+        None if url.scheme() == "mailto" => url
+            .path()
+            .split(',')
+            .next()
+            .and_then(|address| address.rsplit_once('@'))
+            .map(|(_, domain)| domain)
+            .unwrap_or(""),
+        None => "",
+    };
+
+    if hostname.is_empty() {
+        None
+    } else {
+        Some(hostname.to_lowercase())
+    }
+}
+
+/// Query parameters removed by `normalize` unless `$BOOKIT_TRACKING_PARAMETERS` is set.
+const DEFAULT_TRACKING_PARAMETERS: &str =
+    "utm_*,fbclid,gclid,dclid,msclkid,mc_cid,mc_eid,igshid,yclid,_hsenc,_hsmi";
+
+/// Options of the url normalization.
+pub struct NormalizeOptions {
+    /// Names of the query parameters to remove, a trailing `*` matches any suffix.
+    pub tracking_parameters: Vec<String>,
+}
+
+impl NormalizeOptions {
+    /// Reads the tracking parameters from comma separated `$BOOKIT_TRACKING_PARAMETERS`.
+    pub fn from_env() -> NormalizeOptions {
+        let tracking_parameters = std::env::var("BOOKIT_TRACKING_PARAMETERS")
+            .unwrap_or_else(|_| String::from(DEFAULT_TRACKING_PARAMETERS));
+
+        NormalizeOptions {
+            tracking_parameters: tracking_parameters
+                .split(',')
+                .map(str::trim)
+                .filter(|parameter| !parameter.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// Whether a query parameter is a tracking parameter.
+    fn is_tracking_parameter(&self, name: &str) -> bool {
+        self.tracking_parameters
+            .iter()
+            .any(|parameter| match parameter.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => name == parameter,
+            })
+    }
+}
+
+/// Normalizes a bookmark url, returning it with warnings about the url.
+///
+/// Schemes and hosts are lowercased, international domains are stored as punycode, default ports
+/// and tracking parameters are removed. Urls without a scheme are assumed to be `https:` urls and
+/// urls that can't be parsed are returned trimmed.
+pub fn normalize(url: &str, options: &NormalizeOptions) -> (String, Vec<String>) {
+    let url = url.trim();
+    let mut warnings = Vec::new();
+
+    let mut parsed = match url::Url::parse(url) {
+        Ok(parsed) if !is_missing_scheme(url, &parsed) => parsed,
+        _ => match url::Url::parse(&format!("https://{}", url)) {
+            Ok(parsed) => {
+                warnings.push(format!("Url '{}' has no scheme, assuming 'https://'.", url));
+                parsed
+            }
+            Err(_) => {
+                warnings.push(format!("Cannot parse url '{}'.", url));
+                return (String::from(url), warnings);
+            }
+        },
+    };
+
+    // Special schemes like `https:` already have lowercase, punycode hosts.
+    if let Some(host) = parsed.host_str() {
+        let host = host.to_lowercase();
+        let _ = parsed.set_host(Some(&host));
+    }
+
+    // Default ports the url crate doesn't know.
+    if let ("ssh", Some(22)) | ("sftp", Some(22)) = (parsed.scheme(), parsed.port()) {
+        let _ = parsed.set_port(None);
+    }
+
+    // Keep the other parameters as they were written.
+    if parsed.scheme() == "http" || parsed.scheme() == "https" {
+        if let Some(query) = parsed.query() {
+            let parameters: Vec<&str> = query
+                .split('&')
+                .filter(|parameter| {
+                    let name = parameter.split('=').next().unwrap_or("");
+                    !options.is_tracking_parameter(name)
+                })
+                .collect();
+            if parameters.is_empty() {
+                parsed.set_query(None);
+            } else {
+                let query = parameters.join("&");
+                parsed.set_query(Some(&query));
+            }
+        }
+    }
+
+    (String::from(parsed), warnings)
+}
+
+/// Whether a url parsed as `host:port` is missing its scheme, like `localhost:8080/page`.
+fn is_missing_scheme(url: &str, parsed: &url::Url) -> bool {
+    !url.contains("://")
+        && parsed.cannot_be_a_base()
+        && parsed
+            .path()
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Url with an international domain shown in unicode instead of punycode.
+pub fn display(url: &str) -> String {
+    let parsed = match url::Url::parse(url) {
+        Ok(parsed) => parsed,
+        Err(_) => return String::from(url),
+    };
+    let host = match parsed.host_str() {
+        Some(host) if host.split('.').any(|label| label.starts_with("xn--")) => host,
+        _ => return String::from(url),
+    };
+
+    let (unicode_host, result) = idna::domain_to_unicode(host);
+    if result.is_err() {
+        return String::from(url);
+    }
+    url.replacen(host, &unicode_host, 1)
+}
+
+/// Key under which near duplicate urls are equal.
+///
+/// Web urls are compared normalized, ignoring `http:` or `https:`, a `www.` prefix, trailing
+/// slashes and the fragment.
+pub fn duplicate_key(url: &str, options: &NormalizeOptions) -> String {
+    let (url, _) = normalize(url, options);
+    let mut parsed = match url::Url::parse(&url) {
+        Ok(parsed) => parsed,
+        Err(_) => return url,
+    };
+    parsed.set_fragment(None);
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return String::from(parsed);
+    }
+
+    let host = parsed.host_str().unwrap_or("");
+    format!(
+        "{}{}{}{}",
+        host.strip_prefix("www.").unwrap_or(host),
+        parsed
+            .port()
+            .map(|port| format!(":{}", port))
+            .unwrap_or_default(),
+        parsed.path().trim_end_matches('/'),
+        parsed
+            .query()
+            .map(|query| format!("?{}", query))
+            .unwrap_or_default()
+    )
+}